@@ -0,0 +1,315 @@
+//! Statistics for quantifying pattern formation: trail mean and variance, coverage, connectivity of the
+//! thresholded trail, how evenly agents are spread and how aligned their headings are.
+
+use std::io::{self, Write};
+
+use super::boundary::*;
+use super::cell::*;
+use super::plane::*;
+use super::point::*;
+use super::simulation::*;
+use super::trail_map::*;
+
+/// Concentration above which a pixel counts as part of the network
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Threshold {
+    Absolute(Concentration),
+    /// A fraction (between 0-1) of the highest concentration on the map
+    FractionOfMax(f64)
+}
+
+impl Default for Threshold {
+    fn default() -> Self {
+        Threshold::FractionOfMax(0.1f64)
+    }
+}
+
+impl Threshold {
+    pub fn resolve(&self, trail_map: &TrailMap) -> Concentration {
+        match self {
+            Threshold::Absolute(value) => *value,
+            Threshold::FractionOfMax(fraction) => (trail_map.max_value() as f64 * fraction) as Concentration
+        }
+    }
+}
+
+/// Mean and (population) variance of the concentration over the whole map
+pub fn trail_statistics(trail_map: &TrailMap) -> (f64, f64) {
+    let n = trail_map.data.len();
+
+    if n == 0 {
+        return (0f64, 0f64);
+    }
+
+    let mean = trail_map.data.iter().map(|value| *value as f64).sum::<f64>() / n as f64;
+    let variance = trail_map.data.iter().map(|value| (*value as f64 - mean).powi(2)).sum::<f64>() / n as f64;
+
+    (mean, variance)
+}
+
+/// Which pixels are strictly above the threshold
+pub fn threshold_mask(trail_map: &TrailMap, threshold: Threshold) -> Vec<bool> {
+    let level = threshold.resolve(trail_map);
+
+    trail_map.data.iter().map(|value| *value > level).collect()
+}
+
+/// Fraction of the map strictly above the threshold
+pub fn coverage(trail_map: &TrailMap, threshold: Threshold) -> f64 {
+    let mask = threshold_mask(trail_map, threshold);
+
+    if mask.is_empty() {
+        return 0f64;
+    }
+
+    mask.iter().filter(|set| **set).count() as f64 / mask.len() as f64
+}
+
+/// Labels the 8-connected regions of set pixels, wrapping around the edges for periodic boundaries.
+/// Returns one label per pixel (0 for unset pixels, regions numbered from 1) and the number of regions
+pub fn label_components(mask: &[bool], width: usize, height: usize, boundary: BoundaryMode) -> (Vec<usize>, usize) {
+    assert_eq!(mask.len(), width * height, "mask does not match dimensions");
+
+    let mut labels = vec![0usize; mask.len()];
+    let mut count = 0;
+    let mut stack = Vec::new();
+
+    for start in 0..mask.len() {
+        if !mask[start] || labels[start] != 0 {
+            continue;
+        }
+
+        count += 1;
+        labels[start] = count;
+        stack.push(start);
+
+        while let Some(index) = stack.pop() {
+            let (x, y) = ((index % width) as i64, (index / width) as i64);
+
+            for dy in -1..=1i64 {
+                for dx in -1..=1i64 {
+                    let neighbour = match neighbour_index(x + dx, y + dy, width, height, boundary) {
+                        Some(neighbour) => neighbour,
+                        None => continue
+                    };
+
+                    if mask[neighbour] && labels[neighbour] == 0 {
+                        labels[neighbour] = count;
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+    }
+
+    (labels, count)
+}
+
+fn neighbour_index(x: i64, y: i64, width: usize, height: usize, boundary: BoundaryMode) -> Option<usize> {
+    let inside = x >= 0 && y >= 0 && x < width as i64 && y < height as i64;
+
+    if inside {
+        return Some(y as usize * width + x as usize);
+    }
+
+    // only a periodic environment connects opposite edges
+    if boundary == BoundaryMode::Periodic {
+        boundary.resolve(x, y, width, height).map(|point| point.y * width + point.x)
+    } else {
+        None
+    }
+}
+
+/// Number of connected regions of the trail above the threshold
+pub fn connected_components(trail_map: &TrailMap, threshold: Threshold, boundary: BoundaryMode) -> usize {
+    let mask = threshold_mask(trail_map, threshold);
+
+    label_components(&mask, trail_map.width(), trail_map.height(), boundary).1
+}
+
+/// Splits the environment into `tile_size` x `tile_size` tiles (partial tiles at the edges included) and counts
+/// the agents in each, entry `k` of the result is the number of tiles holding exactly `k` agents
+pub fn density_histogram(cell_map: &CellMap, tile_size: usize) -> Vec<usize> {
+    let tile_size = tile_size.max(1);
+    let columns = cell_map.width().div_ceil(tile_size);
+    let rows = cell_map.height().div_ceil(tile_size);
+
+    let mut counts = vec![0usize; columns * rows];
+
+    for index in 0..cell_map.live_cells() {
+        let point = cell_map.pixel(index);
+        counts[(point.y / tile_size) * columns + point.x / tile_size] += 1;
+    }
+
+    let mut histogram = vec![0usize; counts.iter().cloned().max().unwrap_or(0) + 1];
+
+    for count in counts {
+        histogram[count] += 1;
+    }
+
+    histogram
+}
+
+/// Length of the mean heading vector: 1 when every agent points the same way, close to 0 for random headings
+pub fn heading_alignment(cell_map: &CellMap) -> f64 {
+    let n = cell_map.live_cells();
+
+    if n == 0 {
+        return 0f64;
+    }
+
+    let sum = cell_map.directions().iter()
+        .map(|direction| Point::from_degrees(*direction))
+        .fold(Point::new(0f64, 0f64), |sum, heading| sum + heading);
+
+    (sum.x * sum.x + sum.y * sum.y).sqrt() / n as f64
+}
+
+/// Settings for `Metrics::measure`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnalysisConfig {
+    /// Separates the network from the background for coverage and connectivity
+    pub threshold: Threshold,
+    /// Size of the tiles agents are counted in for the density histogram (in pixels)
+    pub tile_size: usize
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            threshold: Threshold::default(),
+            tile_size: 10
+        }
+    }
+}
+
+/// Statistics of a single step of a simulation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metrics {
+    pub step: usize,
+    pub live_cells: usize,
+    pub mean_trail: f64,
+    pub trail_variance: f64,
+    /// Fraction of the environment above the threshold
+    pub coverage: f64,
+    /// Connected regions of the trail above the threshold
+    pub components: usize,
+    /// See `density_histogram`
+    pub density_histogram: Vec<usize>,
+    /// See `heading_alignment`
+    pub heading_alignment: f64
+}
+
+/// Column names of `Metrics::write_csv_row`
+pub const METRICS_CSV_HEADER: &str = "step,live_cells,mean_trail,trail_variance,coverage,components,heading_alignment,density_histogram";
+
+impl Metrics {
+    pub fn measure(simulation: &Simulation, config: &AnalysisConfig) -> Self {
+        let trail_map = &simulation.trail_map;
+        let (mean_trail, trail_variance) = trail_statistics(trail_map);
+
+        Self {
+            step: simulation.step_count,
+            live_cells: simulation.cell_map.live_cells(),
+            mean_trail,
+            trail_variance,
+            coverage: coverage(trail_map, config.threshold),
+            components: connected_components(trail_map, config.threshold, simulation.config.boundary),
+            density_histogram: density_histogram(&simulation.cell_map, config.tile_size),
+            heading_alignment: heading_alignment(&simulation.cell_map)
+        }
+    }
+
+    /// Writes the metrics as a CSV row, the histogram as space separated counts
+    pub fn write_csv_row<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let histogram: Vec<String> = self.density_histogram.iter().map(|count| count.to_string()).collect();
+
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{}",
+            self.step,
+            self.live_cells,
+            self.mean_trail,
+            self.trail_variance,
+            self.coverage,
+            self.components,
+            self.heading_alignment,
+            histogram.join(" ")
+        )
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+    use crate::config::*;
+
+    #[test]
+    fn trail_statistics_and_coverage()
+    {
+        let mut trail_map = TrailMap::new(4, 1);
+        trail_map.data = vec![0.0, 2.0, 4.0, 10.0];
+
+        assert_eq!(trail_statistics(&trail_map), (4.0, 14.0));
+        assert_eq!(coverage(&trail_map, Threshold::Absolute(2.0)), 0.5);
+        assert_eq!(coverage(&trail_map, Threshold::FractionOfMax(0.1)), 0.75);
+        assert_eq!(coverage(&TrailMap::new(3, 3), Threshold::default()), 0.0);
+    }
+
+    #[test]
+    fn components()
+    {
+        let mask = [
+            true,  false, false, true,
+            false, true,  false, false,
+            false, false, false, true,
+        ].to_vec();
+
+        // the diagonal pair is one region, the right hand pixels only touch across the edge
+        let (labels, count) = label_components(&mask, 4, 3, BoundaryMode::Absorbing);
+        assert_eq!(count, 3);
+        assert_eq!(labels[0], labels[5]);
+
+        // wrapping joins the right column to the left one, and the top row to the bottom
+        assert_eq!(label_components(&mask, 4, 3, BoundaryMode::Periodic).1, 1);
+    }
+
+    #[test]
+    fn agents()
+    {
+        let mut cell_map = CellMap::new(4, 4, SensorConfig::default()).unwrap();
+        cell_map.add_cell(Point::new(0f64, 0f64), 90f64);
+        cell_map.add_cell(Point::new(1f64, 1f64), 90f64);
+        cell_map.add_cell(Point::new(3f64, 3f64), 90f64);
+
+        // two tiles are empty, one holds one agent and one holds two
+        assert_eq!(density_histogram(&cell_map, 2), vec![2, 1, 1]);
+        assert!((heading_alignment(&cell_map) - 1.0).abs() < 1e-9);
+
+        cell_map.add_cell(Point::new(2f64, 0f64), 270f64);
+        cell_map.add_cell(Point::new(0f64, 2f64), 270f64);
+        cell_map.add_cell(Point::new(2f64, 2f64), 270f64);
+
+        assert!(heading_alignment(&cell_map) < 1e-9);
+    }
+
+    #[test]
+    fn measure()
+    {
+        let config = SimulationConfig { width: 20, height: 20, ..SimulationConfig::default() };
+        let mut simulation = Simulation::new_random(config, 0.1f64).unwrap();
+        simulation.step(3);
+
+        let metrics = Metrics::measure(&simulation, &AnalysisConfig::default());
+
+        assert_eq!(metrics.step, 3);
+        assert_eq!(metrics.live_cells, simulation.cell_map.live_cells());
+        assert_eq!(metrics.density_histogram.iter().sum::<usize>(), 4);
+
+        let mut row = Vec::new();
+        metrics.write_csv_row(&mut row).unwrap();
+
+        assert_eq!(String::from_utf8(row).unwrap().split(',').count(), METRICS_CSV_HEADER.split(',').count());
+    }
+}
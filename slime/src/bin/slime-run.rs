@@ -0,0 +1,335 @@
+//! Headless runner: steps a simulation and periodically writes the trail and cell maps to disk.
+
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+
+use slime::analysis::{AnalysisConfig, Metrics, METRICS_CSV_HEADER};
+use slime::config::SimulationConfig;
+use slime::image::encode_pgm;
+use slime::layout::Layout;
+use slime::network::{Network, NetworkConfig};
+use slime::proximity::compare_all;
+use slime::recorder::{FrameSource, Recorder};
+use slime::plane::Plane;
+use slime::simulation::Simulation;
+use slime::texture::{Texture, TextureConfig, TEXTURE_CSV_COLUMNS};
+
+const USAGE: &str = "Usage: slime-run [options]
+
+Options:
+    --config <FILE>   Read the simulation parameters from a TOML or JSON file
+    --preset <NAME>   Start from a named parameter preset: network, reticular, labyrinth, spots or coarse
+    --width <N>       Width of the environment (default: 200)
+    --height <N>      Height of the environment (default: 200)
+    --steps <N>       Number of simulation steps to run (default: 1000)
+    --interval <N>    Write images every N steps, 0 to only write the final state (default: 100)
+    --format <F>      Image format, png or pgm (default: png)
+    --gif <FILE>      Also record an animated GIF with a frame every interval
+    --metrics <FILE>  Write trail and agent statistics every interval to a CSV file
+    --texture <FILE>  Write the fractal dimension, lacunarity and power spectrum of the trail every
+                      interval to a CSV file
+    --network <FILE>  Write the final transport network as a graph, .graphml, .geojson or .dot,
+                      and score it against proximity graphs of the food sources
+    --composite       Record the composite view (trail, obstacles, food and cells) in the GIF
+    --density <P>     Probability of a pixel starting with a cell (default: 0.1)
+    --decay <F>       Fraction of the trail that evaporates each step (default: 0)
+    --seed <N>        Seed for the random number generator (default: 0)
+                      (--width, --height, --decay and --seed override the config file or preset)
+    --layout <FILE>   Build the environment from a PNG/PGM/PPM image instead of placing cells randomly,
+                      see the `layout` module for the colour legend
+    --resume <FILE>   Continue a run from a snapshot, ignoring the environment options above
+    --snapshot <FILE> Save a snapshot of the final state, to continue the run later with --resume
+    --output <DIR>    Directory to write images to (default: output)";
+
+#[derive(Clone, Copy)]
+enum Format {
+    Png,
+    Pgm
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "png" => Ok(Format::Png),
+            "pgm" => Ok(Format::Pgm),
+            _ => Err(format!("unknown format: {}", value))
+        }
+    }
+}
+
+struct Options {
+    config: Option<PathBuf>,
+    preset: Option<String>,
+    width: Option<usize>,
+    height: Option<usize>,
+    steps: usize,
+    interval: usize,
+    format: Format,
+    gif: Option<PathBuf>,
+    metrics: Option<PathBuf>,
+    texture: Option<PathBuf>,
+    network: Option<PathBuf>,
+    composite: bool,
+    density: f64,
+    decay: Option<f64>,
+    seed: Option<u64>,
+    layout: Option<PathBuf>,
+    resume: Option<PathBuf>,
+    snapshot: Option<PathBuf>,
+    output: PathBuf
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            config: None,
+            preset: None,
+            width: None,
+            height: None,
+            steps: 1000,
+            interval: 100,
+            format: Format::Png,
+            gif: None,
+            metrics: None,
+            texture: None,
+            network: None,
+            composite: false,
+            density: 0.1f64,
+            decay: None,
+            seed: None,
+            layout: None,
+            resume: None,
+            snapshot: None,
+            output: PathBuf::from("output")
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing value for {}", flag))?;
+
+    value.parse::<T>().map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--config" => options.config = Some(parse_value(&flag, args.next())?),
+            "--preset" => options.preset = Some(parse_value(&flag, args.next())?),
+            "--width" => options.width = Some(parse_value(&flag, args.next())?),
+            "--height" => options.height = Some(parse_value(&flag, args.next())?),
+            "--steps" => options.steps = parse_value(&flag, args.next())?,
+            "--interval" => options.interval = parse_value(&flag, args.next())?,
+            "--format" => options.format = parse_value(&flag, args.next())?,
+            "--gif" => options.gif = Some(parse_value(&flag, args.next())?),
+            "--metrics" => options.metrics = Some(parse_value(&flag, args.next())?),
+            "--texture" => options.texture = Some(parse_value(&flag, args.next())?),
+            "--network" => options.network = Some(parse_value(&flag, args.next())?),
+            "--composite" => options.composite = true,
+            "--density" => options.density = parse_value(&flag, args.next())?,
+            "--decay" => options.decay = Some(parse_value(&flag, args.next())?),
+            "--seed" => options.seed = Some(parse_value(&flag, args.next())?),
+            "--layout" => options.layout = Some(parse_value(&flag, args.next())?),
+            "--resume" => options.resume = Some(parse_value(&flag, args.next())?),
+            "--snapshot" => options.snapshot = Some(parse_value(&flag, args.next())?),
+            "--output" => options.output = parse_value(&flag, args.next())?,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            },
+            _ => return Err(format!("unknown argument: {}", flag))
+        }
+    }
+
+    Ok(options)
+}
+
+fn write_frame(simulation: &Simulation, output: &Path, format: Format, step: usize) -> Result<(), Box<dyn Error>> {
+    let trail_map = &simulation.trail_map;
+    let cell_map = &simulation.cell_map;
+
+    let (trail, cells, extension) = match format {
+        Format::Png => (trail_map.to_png()?, cell_map.to_png()?, "png"),
+        Format::Pgm => (
+            encode_pgm(trail_map.width(), trail_map.height(), &trail_map.to_grayscale()),
+            encode_pgm(cell_map.width(), cell_map.height(), &cell_map.to_grayscale()),
+            "pgm"
+        )
+    };
+
+    fs::write(output.join(format!("trail_{:06}.{}", step, extension)), trail)?;
+    fs::write(output.join(format!("cells_{:06}.{}", step, extension)), cells)?;
+
+    Ok(())
+}
+
+fn write_network(simulation: &Simulation, path: &Path) -> Result<(), Box<dyn Error>> {
+    let network = Network::from_simulation(simulation, &NetworkConfig::default());
+
+    let text = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("graphml") => network.to_graphml(),
+        Some("geojson") | Some("json") => network.to_geojson(),
+        Some("dot") | Some("gv") => network.to_dot(),
+        _ => return Err(format!("unknown network format: {}", path.display()).into())
+    };
+
+    fs::write(path, text)?;
+    println!("network: {} nodes, {} edges", network.nodes.len(), network.edges.len());
+
+    let food: Vec<_> = simulation.nutrients.iter().map(|source| source.centre()).collect();
+
+    if food.len() >= 2 {
+        println!("graph     precision  recall  length ratio  fault tolerance (network / graph)");
+
+        for comparison in compare_all(&network, &food) {
+            println!(
+                "{:<9} {:>9.3} {:>7.3} {:>13.3} {:>9.3} / {:.3}",
+                comparison.kind.name(),
+                comparison.precision,
+                comparison.recall,
+                comparison.length_ratio,
+                comparison.network_fault_tolerance,
+                comparison.reference_fault_tolerance
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let mut config = match (&options.config, &options.preset) {
+        (Some(path), _) => SimulationConfig::load(path)?,
+        (None, Some(name)) => SimulationConfig::preset(name)?,
+        (None, None) => SimulationConfig { width: 200, height: 200, ..SimulationConfig::default() }
+    };
+
+    config.width = options.width.unwrap_or(config.width);
+    config.height = options.height.unwrap_or(config.height);
+    config.decay = options.decay.unwrap_or(config.decay);
+    config.seed = options.seed.unwrap_or(config.seed);
+    config.validate()?;
+
+    let mut simulation = match (&options.resume, &options.layout) {
+        (Some(path), _) => Simulation::load_snapshot(path)?,
+        (None, Some(path)) => Layout::load(path)?.into_simulation(config)?,
+        (None, None) => Simulation::new_random(config, options.density)?
+    };
+
+    fs::create_dir_all(&options.output)?;
+
+    let mut recorder = match &options.gif {
+        Some(path) => {
+            let source = if options.composite { FrameSource::Composite } else { FrameSource::Trail };
+            let mut recorder = Recorder::gif_file(path, &simulation, options.interval)?
+                .with_source(source);
+
+            recorder.capture(&simulation)?;
+
+            Some(recorder)
+        },
+        None => None
+    };
+
+    let analysis = AnalysisConfig::default();
+
+    let mut metrics = match &options.metrics {
+        Some(path) => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            writeln!(writer, "{}", METRICS_CSV_HEADER)?;
+            Metrics::measure(&simulation, &analysis).write_csv_row(&mut writer)?;
+
+            Some(writer)
+        },
+        None => None
+    };
+
+    let texture_config = TextureConfig::default();
+
+    let mut texture = match &options.texture {
+        Some(path) => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            writeln!(writer, "step,{}", TEXTURE_CSV_COLUMNS)?;
+            Texture::from_simulation(&simulation, &texture_config).write_csv_row(simulation.step_count, &mut writer)?;
+
+            Some(writer)
+        },
+        None => None
+    };
+
+    let mut step = 0;
+
+    while step < options.steps {
+        let n = if options.interval == 0 {
+            options.steps
+        } else {
+            options.interval.min(options.steps - step)
+        };
+
+        simulation.step(n);
+        step += n;
+
+        if options.interval != 0 || step == options.steps {
+            write_frame(&simulation, &options.output, options.format, simulation.step_count)?;
+            println!("step {}: {} live cells", simulation.step_count, simulation.cell_map.live_cells());
+        }
+
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.capture(&simulation)?;
+        }
+
+        if let Some(writer) = metrics.as_mut() {
+            Metrics::measure(&simulation, &analysis).write_csv_row(writer)?;
+        }
+
+        if let Some(writer) = texture.as_mut() {
+            Texture::from_simulation(&simulation, &texture_config).write_csv_row(simulation.step_count, writer)?;
+        }
+    }
+
+    if let Some(mut writer) = metrics {
+        writer.flush()?;
+    }
+
+    if let Some(mut writer) = texture {
+        writer.flush()?;
+    }
+
+    if let Some(recorder) = recorder {
+        recorder.finish()?;
+    }
+
+    if let Some(path) = &options.network {
+        write_network(&simulation, path)?;
+    }
+
+    if let Some(path) = &options.snapshot {
+        simulation.save_snapshot(path)?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(1);
+        }
+    };
+
+    if let Err(error) = run(options) {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,248 @@
+//! Batch runner: sweeps simulation parameters, writing a CSV of summary metrics and a thumbnail per run, and a
+//! phase diagram of the patterns the runs formed.
+
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+
+use slime::config::SimulationConfig;
+use slime::sweep::{parse_values, phase_diagram, write_csv_row, Sweep, SweepAxis, CSV_HEADER};
+use slime::trail_map::Concentration;
+
+const USAGE: &str = "Usage: slime-sweep [options]
+
+Parameter values are a comma separated list (22.5,45,90) or an inclusive range start:end:step (0:90:22.5),
+parameters that are not given keep the value from the base config.
+
+Options:
+    --config <FILE>           Base simulation parameters from a TOML or JSON file
+    --preset <NAME>           Base simulation parameters from a named preset (default: network)
+    --width <N>               Width of the environment (default: from the base config)
+    --height <N>              Height of the environment (default: from the base config)
+    --seed <N>                Seed shared by every run (default: from the base config)
+    --steps <N>               Steps per run (default: 1000)
+    --density <P>             Probability of a pixel starting with a cell (default: 0.1)
+    --sensor-angle <VALUES>   Angles between the sensors (degrees)
+    --rotation-angle <VALUES> Rotation angles (degrees)
+    --sensor-offset <VALUES>  Sensor offset distances (pixels)
+    --step-size <VALUES>      Distances moved per step (pixels)
+    --deposition <VALUES>     Chemoattractant deposited per step
+    --decay <VALUES>          Fractions of the trail evaporating each step
+    --thumbnail <N>           Longest side of the thumbnails (default: 64)
+    --frames <N>              Trail maps at the end of each run the pattern is classified on (default: 5)
+    --frame-interval <N>      Steps between those trail maps (default: 25)
+    --phase <ROW>,<COLUMN>    Parameters along the phase diagram, e.g. sensor-angle,rotation-angle
+                              (default: the first two that take more than one value)
+    --output <DIR>            Directory for results.csv, phase_diagram.csv and the thumbnails (default: sweep)";
+
+struct Options {
+    config: Option<PathBuf>,
+    preset: String,
+    width: Option<usize>,
+    height: Option<usize>,
+    seed: Option<u64>,
+    steps: usize,
+    density: f64,
+    sensor_angles: Option<Vec<f64>>,
+    rotation_angles: Option<Vec<f64>>,
+    sensor_offsets: Option<Vec<usize>>,
+    step_sizes: Option<Vec<usize>>,
+    depositions: Option<Vec<Concentration>>,
+    decays: Option<Vec<f64>>,
+    thumbnail: usize,
+    frames: usize,
+    frame_interval: usize,
+    phase: Option<(SweepAxis, SweepAxis)>,
+    output: PathBuf
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            config: None,
+            preset: String::from("network"),
+            width: None,
+            height: None,
+            seed: None,
+            steps: 1000,
+            density: 0.1f64,
+            sensor_angles: None,
+            rotation_angles: None,
+            sensor_offsets: None,
+            step_sizes: None,
+            depositions: None,
+            decays: None,
+            thumbnail: 64,
+            frames: 5,
+            frame_interval: 25,
+            phase: None,
+            output: PathBuf::from("sweep")
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing value for {}", flag))?;
+
+    value.parse::<T>().map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+fn parse_list(flag: &str, value: Option<String>) -> Result<Vec<f64>, String> {
+    let value = value.ok_or_else(|| format!("missing value for {}", flag))?;
+
+    parse_values(&value).map_err(|message| format!("{}: {}", flag, message))
+}
+
+fn parse_pixels(flag: &str, value: Option<String>) -> Result<Vec<usize>, String> {
+    parse_list(flag, value)?
+        .into_iter()
+        .map(|value| {
+            if value >= 0f64 && value.fract() == 0f64 {
+                Ok(value as usize)
+            } else {
+                Err(format!("{} only takes whole numbers of pixels, got {}", flag, value))
+            }
+        })
+        .collect()
+}
+
+fn parse_axes(flag: &str, value: Option<String>) -> Result<(SweepAxis, SweepAxis), String> {
+    let value = value.ok_or_else(|| format!("missing value for {}", flag))?;
+    let axis = |name: &str| SweepAxis::from_name(name.trim()).ok_or_else(|| format!("{}: unknown parameter {}", flag, name));
+
+    match value.split(',').collect::<Vec<&str>>().as_slice() {
+        [row, column] => Ok((axis(row)?, axis(column)?)),
+        _ => Err(format!("{} takes two parameters separated by a comma, got {}", flag, value))
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--config" => options.config = Some(parse_value(&flag, args.next())?),
+            "--preset" => options.preset = parse_value(&flag, args.next())?,
+            "--width" => options.width = Some(parse_value(&flag, args.next())?),
+            "--height" => options.height = Some(parse_value(&flag, args.next())?),
+            "--seed" => options.seed = Some(parse_value(&flag, args.next())?),
+            "--steps" => options.steps = parse_value(&flag, args.next())?,
+            "--density" => options.density = parse_value(&flag, args.next())?,
+            "--sensor-angle" => options.sensor_angles = Some(parse_list(&flag, args.next())?),
+            "--rotation-angle" => options.rotation_angles = Some(parse_list(&flag, args.next())?),
+            "--sensor-offset" => options.sensor_offsets = Some(parse_pixels(&flag, args.next())?),
+            "--step-size" => options.step_sizes = Some(parse_pixels(&flag, args.next())?),
+            "--deposition" => {
+                let values = parse_list(&flag, args.next())?;
+                options.depositions = Some(values.into_iter().map(|value| value as Concentration).collect());
+            },
+            "--decay" => options.decays = Some(parse_list(&flag, args.next())?),
+            "--thumbnail" => options.thumbnail = parse_value(&flag, args.next())?,
+            "--frames" => options.frames = parse_value(&flag, args.next())?,
+            "--frame-interval" => options.frame_interval = parse_value(&flag, args.next())?,
+            "--phase" => options.phase = Some(parse_axes(&flag, args.next())?),
+            "--output" => options.output = parse_value(&flag, args.next())?,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            },
+            _ => return Err(format!("unknown argument: {}", flag))
+        }
+    }
+
+    Ok(options)
+}
+
+fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let mut base = match &options.config {
+        Some(path) => SimulationConfig::load(path)?,
+        None => SimulationConfig::preset(&options.preset)?
+    };
+
+    base.width = options.width.unwrap_or(base.width);
+    base.height = options.height.unwrap_or(base.height);
+    base.seed = options.seed.unwrap_or(base.seed);
+    base.validate()?;
+
+    let mut sweep = Sweep::new(base);
+    sweep.steps = options.steps;
+    sweep.density = options.density;
+    sweep.thumbnail_size = options.thumbnail;
+    sweep.pattern_frames = options.frames;
+    sweep.frame_interval = options.frame_interval;
+
+    if let Some(values) = options.sensor_angles {
+        sweep.sensor_angles = values;
+    }
+    if let Some(values) = options.rotation_angles {
+        sweep.rotation_angles = values;
+    }
+    if let Some(values) = options.sensor_offsets {
+        sweep.sensor_offsets = values;
+    }
+    if let Some(values) = options.step_sizes {
+        sweep.step_sizes = values;
+    }
+    if let Some(values) = options.depositions {
+        sweep.depositions = values;
+    }
+    if let Some(values) = options.decays {
+        sweep.decays = values;
+    }
+
+    let (rows, columns) = options.phase.unwrap_or_else(|| match sweep.varied_axes().as_slice() {
+        [rows, columns, ..] => (*rows, *columns),
+        [axis] if *axis != SweepAxis::SensorAngle => (SweepAxis::SensorAngle, *axis),
+        _ => (SweepAxis::SensorAngle, SweepAxis::RotationAngle)
+    });
+
+    println!("running {} combinations of {} steps", sweep.combinations()?.len(), sweep.steps);
+
+    let output = &options.output;
+    fs::create_dir_all(output)?;
+
+    let mut csv = BufWriter::new(File::create(output.join("results.csv"))?);
+    writeln!(csv, "{}", CSV_HEADER)?;
+
+    // every run is written out as soon as it is in order, only the summaries are kept for the phase diagram
+    let mut results = Vec::new();
+
+    sweep.run_each(|mut result| {
+        fs::write(output.join(format!("run_{:04}.png", result.index)), result.thumbnail_png()?)?;
+
+        write_csv_row(&result, &mut csv)?;
+        csv.flush()?;
+
+        result.thumbnail = Vec::new();
+        results.push(result);
+
+        Ok::<(), Box<dyn Error>>(())
+    })?;
+
+    let diagram = phase_diagram(&results, rows, columns);
+    fs::write(output.join("phase_diagram.csv"), &diagram)?;
+
+    println!("{}", diagram);
+    println!("wrote {}", output.join("results.csv").display());
+
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(1);
+        }
+    };
+
+    if let Err(error) = run(options) {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
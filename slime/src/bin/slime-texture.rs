@@ -0,0 +1,129 @@
+//! Measures the texture descriptors of images, e.g. photographs of real plasmodia, in the same CSV format as
+//! `slime-run --texture` so they can be compared with simulated networks.
+
+use std::env;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+
+use slime::analysis::Threshold;
+use slime::layout::Layout;
+use slime::sweep::parse_values;
+use slime::texture::{trail_from_image, Texture, TextureConfig, TEXTURE_CSV_COLUMNS};
+
+const USAGE: &str = "Usage: slime-texture [options] <IMAGE>...
+
+Writes one CSV row per PNG, PGM or PPM image to standard output.
+
+Options:
+    --threshold <F>     Fraction of the brightest pixel above which a pixel belongs to the pattern (default: 0.5)
+    --invert            The pattern is darker than the background
+    --box-sizes <LIST>  Box sizes for the fractal dimension and lacunarity, e.g. 1,2,4,8
+                        (default: doubling from 1 up to a quarter of the shorter side)";
+
+struct Options {
+    threshold: f64,
+    invert: bool,
+    box_sizes: Vec<usize>,
+    images: Vec<PathBuf>
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5f64,
+            invert: false,
+            box_sizes: Vec::new(),
+            images: Vec::new()
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing value for {}", flag))?;
+
+    value.parse::<T>().map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+fn parse_sizes(flag: &str, value: Option<String>) -> Result<Vec<usize>, String> {
+    let value = value.ok_or_else(|| format!("missing value for {}", flag))?;
+
+    parse_values(&value)
+        .map_err(|message| format!("{}: {}", flag, message))?
+        .into_iter()
+        .map(|size| {
+            if size >= 1f64 && size.fract() == 0f64 {
+                Ok(size as usize)
+            } else {
+                Err(format!("{} only takes whole numbers of pixels, got {}", flag, size))
+            }
+        })
+        .collect()
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--threshold" => options.threshold = parse_value(&flag, args.next())?,
+            "--invert" => options.invert = true,
+            "--box-sizes" => options.box_sizes = parse_sizes(&flag, args.next())?,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            },
+            _ if flag.starts_with("--") => return Err(format!("unknown argument: {}", flag)),
+            _ => options.images.push(PathBuf::from(flag))
+        }
+    }
+
+    if !(0f64..=1f64).contains(&options.threshold) {
+        return Err(format!("--threshold must be between 0 and 1, got {}", options.threshold));
+    }
+
+    if options.images.is_empty() {
+        return Err(String::from("no images given"));
+    }
+
+    Ok(options)
+}
+
+fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let config = TextureConfig {
+        threshold: Threshold::FractionOfMax(options.threshold),
+        box_sizes: options.box_sizes
+    };
+
+    let stdout = io::stdout();
+    let mut writer = stdout.lock();
+
+    writeln!(writer, "image,{}", TEXTURE_CSV_COLUMNS)?;
+
+    for path in options.images.iter() {
+        let trail_map = trail_from_image(&Layout::load(path)?, options.invert);
+
+        Texture::measure(&trail_map, &config).write_csv_row(path.display(), &mut writer)?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(1);
+        }
+    };
+
+    if let Err(error) = run(options) {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
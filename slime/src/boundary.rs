@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+use super::point::*;
+
+/// How the edges of the environment behave for agents, sensors and diffusion
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoundaryMode {
+    /// The environment wraps around on both axes (toroidal)
+    Periodic,
+    /// Agents bounce off the edges, sensors and diffusion see a mirror image of the environment
+    Reflective,
+    /// Agents that cross an edge die, everything outside the environment reads as empty
+    Absorbing,
+    /// Agents are held at the edge, sensors read the nearest edge pixel and diffusion ignores the outside. The
+    /// default, as the environment behaved before boundary modes were configurable
+    #[default]
+    Clamped
+}
+
+fn mirror(value: i64, size: i64) -> i64 {
+    if size == 1 {
+        return 0;
+    }
+
+    // reflect about the first and last pixel, a full period is 2 * (size - 1)
+    let period = 2 * (size - 1);
+    let folded = value.rem_euclid(period);
+
+    if folded < size { folded } else { period - folded }
+}
+
+impl BoundaryMode {
+    /// Maps a (possibly out of bounds) pixel coordinate to a pixel inside the environment,
+    /// `None` means the coordinate is outside and should be treated as empty
+    pub fn resolve(self, x: i64, y: i64, width: usize, height: usize) -> Option<Point<usize>> {
+        let (w, h) = (width as i64, height as i64);
+
+        let (x, y) = match self {
+            BoundaryMode::Periodic => (x.rem_euclid(w), y.rem_euclid(h)),
+            BoundaryMode::Reflective => (mirror(x, w), mirror(y, h)),
+            BoundaryMode::Clamped => (x.clamp(0, w - 1), y.clamp(0, h - 1)),
+            BoundaryMode::Absorbing => {
+                if x < 0 || y < 0 || x >= w || y >= h {
+                    return None;
+                }
+
+                (x, y)
+            }
+        };
+
+        Some(Point::new(x as usize, y as usize))
+    }
+
+    /// Applies the boundary to an agent that moved to `position` while facing `direction` (in degrees),
+    /// returns the corrected position and direction, or `None` if the agent is absorbed
+    pub fn constrain(self, position: Point<f64>, direction: f64, width: usize, height: usize) -> Option<(Point<f64>, f64)> {
+        let (w, h) = (width as f64, height as f64);
+        let (max_x, max_y) = (w - 1f64, h - 1f64);
+
+        match self {
+            BoundaryMode::Periodic => {
+                Some((Point::new(position.x.rem_euclid(w), position.y.rem_euclid(h)), direction))
+            },
+            BoundaryMode::Reflective => {
+                let mut position = position;
+                let mut direction = direction;
+
+                if position.x < 0f64 || position.x > max_x {
+                    position.x = if position.x < 0f64 { -position.x } else { 2f64 * max_x - position.x };
+                    direction = 180f64 - direction;
+                }
+
+                if position.y < 0f64 || position.y > max_y {
+                    position.y = if position.y < 0f64 { -position.y } else { 2f64 * max_y - position.y };
+                    direction = -direction;
+                }
+
+                // a step longer than the environment could still overshoot, keep it inside regardless
+                position.x = position.x.clamp(0f64, max_x);
+                position.y = position.y.clamp(0f64, max_y);
+
+                Some((position, direction.rem_euclid(360f64)))
+            },
+            BoundaryMode::Clamped => {
+                Some((Point::new(position.x.clamp(0f64, max_x), position.y.clamp(0f64, max_y)), direction))
+            },
+            BoundaryMode::Absorbing => {
+                let discrete: Point<i64> = position.into();
+
+                if discrete.x < 0 || discrete.y < 0 || discrete.x >= width as i64 || discrete.y >= height as i64 {
+                    None
+                } else {
+                    Some((position, direction))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    #[test]
+    fn resolve()
+    {
+        assert_eq!(BoundaryMode::Periodic.resolve(-1, 10, 10, 10), Some(Point::new(9, 0)));
+        assert_eq!(BoundaryMode::Reflective.resolve(-1, 10, 10, 10), Some(Point::new(1, 8)));
+        assert_eq!(BoundaryMode::Clamped.resolve(-1, 10, 10, 10), Some(Point::new(0, 9)));
+        assert_eq!(BoundaryMode::Absorbing.resolve(-1, 10, 10, 10), None);
+        assert_eq!(BoundaryMode::Absorbing.resolve(3, 4, 10, 10), Some(Point::new(3, 4)));
+    }
+
+    #[test]
+    fn constrain()
+    {
+        let outside = Point::new(10.5f64, 2f64);
+
+        let (position, direction) = BoundaryMode::Periodic.constrain(outside, 0f64, 10, 10).unwrap();
+        assert_eq!((position, direction), (Point::new(0.5f64, 2f64), 0f64));
+
+        let (position, direction) = BoundaryMode::Reflective.constrain(outside, 0f64, 10, 10).unwrap();
+        assert_eq!((position, direction), (Point::new(7.5f64, 2f64), 180f64));
+
+        let (position, direction) = BoundaryMode::Clamped.constrain(outside, 0f64, 10, 10).unwrap();
+        assert_eq!((position, direction), (Point::new(9f64, 2f64), 0f64));
+
+        assert!(BoundaryMode::Absorbing.constrain(outside, 0f64, 10, 10).is_none());
+    }
+}
@@ -0,0 +1,396 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "web")]
+use web_sys::ImageData;
+#[cfg(feature = "web")]
+use wasm_bindgen::Clamped;
+
+use super::boundary::*;
+use super::config::*;
+use super::image::*;
+use super::point::*;
+
+#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Cell {
+    /// The pixel position of the cell
+    pub position: Point<f64>,
+    /// Direction in which the cell is "pointed", this determines what is in its field of view
+    pub direction: f64
+}
+
+impl Cell {
+    pub fn position_discrete(&self) -> Point<usize> {
+        self.position.into()
+    }
+
+    /// Updates the position of the cell according to the current direction + passed distance
+    pub fn step(&mut self, distance: f64)
+    {
+        self.position = self.position + (Point::from_degrees(self.direction) * distance);
+    }
+}
+
+/// Marks a free pixel in the occupancy grid
+const EMPTY: u32 = u32::MAX;
+
+/// The agents in structure-of-arrays form: agent `i` is at `positions[i]`, heading `directions[i]`, and occupies
+/// the pixel `pixels[i]` (row-major index). A dense occupancy grid maps every pixel back to the agent on it, at most
+/// one agent occupies a pixel.
+///
+/// Removing an agent moves the last agent into its slot, so indices are only stable while no agent is removed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CellMap {
+    positions: Vec<Point<f64>>,
+    directions: Vec<f64>,
+    pixels: Vec<u32>,
+    /// Agent index per pixel, `EMPTY` for free pixels. Not stored, see `rebuild_occupancy`
+    #[serde(skip)]
+    occupancy: Vec<u32>,
+    /// Arrays `sort_by_pixel` fills and swaps in, kept to avoid allocating on every sort. Empty between sorts
+    #[serde(skip)]
+    sorted_positions: Vec<Point<f64>>,
+    #[serde(skip)]
+    sorted_directions: Vec<f64>,
+    #[serde(skip)]
+    sorted_pixels: Vec<u32>,
+    width: usize,
+    height: usize,
+    #[allow(dead_code)]
+    sensor_config: SensorConfig
+}
+
+impl CellMap {
+    /// An empty map, fails when the environment has more pixels than the occupancy grid can index (see `MAX_AREA`)
+    pub fn new(width: usize, height: usize, sensor_config: SensorConfig) -> Result<Self, ConfigError> {
+        let area = check_area(width, height)?;
+
+        Ok(Self {
+            positions: Vec::new(),
+            directions: Vec::new(),
+            pixels: Vec::new(),
+            occupancy: vec![EMPTY; area],
+            sorted_positions: Vec::new(),
+            sorted_directions: Vec::new(),
+            sorted_pixels: Vec::new(),
+            width,
+            height,
+            sensor_config
+        })
+    }
+
+    pub fn new_random<R: Rng>(width: usize, height: usize, sensor_config: SensorConfig, probability: f64, rng: &mut R) -> Result<Self, ConfigError> {
+        let mut cell_map = Self::new(width, height, sensor_config)?;
+
+        for y in 0..height {
+            for x in 0..width {
+                if rng.gen::<f64>() < probability {
+                    let position = Point { x: x as f64, y: y as f64 };
+                    let direction = rng.gen::<f64>() * 360f64;
+
+                    cell_map.push(position, direction, y * width + x);
+                }
+            }
+        }
+
+        Ok(cell_map)
+    }
+
+    fn push(&mut self, position: Point<f64>, direction: f64, pixel: usize) {
+        self.occupancy[pixel] = self.positions.len() as u32;
+        self.positions.push(position);
+        self.directions.push(direction);
+        self.pixels.push(pixel as u32);
+    }
+
+    /// Places a cell on the pixel nearest to `position`, replacing any cell already there. Positions that round
+    /// to a pixel outside the map are ignored
+    pub fn add_cell(&mut self, position: Point<f64>, direction: f64) {
+        let discrete: Point<i64> = position.into();
+
+        if discrete.x < 0 || discrete.y < 0 || discrete.x >= self.width as i64 || discrete.y >= self.height as i64 {
+            return;
+        }
+
+        let pixel = discrete.y as usize * self.width + discrete.x as usize;
+
+        match self.occupancy[pixel] {
+            EMPTY => self.push(position, direction, pixel),
+            index => {
+                self.positions[index as usize] = position;
+                self.directions[index as usize] = direction;
+            }
+        }
+    }
+
+    /// The cell occupying a pixel
+    pub fn get_cell(&self, position: Point<usize>) -> Option<Cell> {
+        self.index_at(position).map(|index| self.cell(index))
+    }
+
+    /// Index of the cell occupying a pixel
+    pub fn index_at(&self, position: Point<usize>) -> Option<usize> {
+        if position.x >= self.width || position.y >= self.height {
+            return None;
+        }
+
+        match self.occupancy[position.y * self.width + position.x] {
+            EMPTY => None,
+            index => Some(index as usize)
+        }
+    }
+
+    pub fn is_occupied(&self, position: Point<usize>) -> bool {
+        self.index_at(position).is_some()
+    }
+
+    pub fn cell(&self, index: usize) -> Cell {
+        Cell { position: self.positions[index], direction: self.directions[index] }
+    }
+
+    /// Every cell, in storage order
+    pub fn cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        self.positions.iter().zip(self.directions.iter()).map(|(position, direction)| Cell { position: *position, direction: *direction })
+    }
+
+    pub fn positions(&self) -> &[Point<f64>] {
+        &self.positions
+    }
+
+    pub fn directions(&self) -> &[f64] {
+        &self.directions
+    }
+
+    /// Headings can change freely, positions only through `move_cell` to keep the occupancy grid in sync
+    pub fn directions_mut(&mut self) -> &mut [f64] {
+        &mut self.directions
+    }
+
+    /// Positions alongside mutable headings, for updating every heading in one pass
+    pub fn headings_mut(&mut self) -> (&[Point<f64>], &mut [f64]) {
+        (&self.positions, &mut self.directions)
+    }
+
+    /// The pixel a cell occupies
+    pub fn pixel(&self, index: usize) -> Point<usize> {
+        let pixel = self.pixels[index] as usize;
+
+        Point::new(pixel % self.width, pixel / self.width)
+    }
+
+    /// Moves a cell to `position`, occupying `pixel` which has to be free (or the cell's own)
+    pub fn move_cell(&mut self, index: usize, position: Point<f64>, pixel: Point<usize>) {
+        let (old, new) = (self.pixels[index] as usize, pixel.y * self.width + pixel.x);
+        debug_assert!(self.occupancy[new] == EMPTY || old == new, "pixel is already occupied");
+
+        self.occupancy[old] = EMPTY;
+        self.occupancy[new] = index as u32;
+        self.pixels[index] = new as u32;
+        self.positions[index] = position;
+    }
+
+    /// Removes a cell, the last cell takes over its index
+    pub fn remove_cell(&mut self, index: usize) {
+        self.occupancy[self.pixels[index] as usize] = EMPTY;
+
+        self.positions.swap_remove(index);
+        self.directions.swap_remove(index);
+        self.pixels.swap_remove(index);
+
+        if index < self.pixels.len() {
+            self.occupancy[self.pixels[index] as usize] = index as u32;
+        }
+    }
+
+    /// Removes several cells at once, `indices` have to be distinct
+    pub fn remove_cells(&mut self, indices: &mut [usize]) {
+        // removing from the back first keeps the remaining indices valid
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+
+        for index in indices.iter() {
+            self.remove_cell(*index);
+        }
+    }
+
+    /// Reorders the cells row-major by pixel, so cells stored next to each other are also close in the environment
+    pub fn sort_by_pixel(&mut self) {
+        for (pixel, slot) in self.occupancy.iter_mut().enumerate() {
+            if *slot != EMPTY {
+                let index = *slot as usize;
+                *slot = self.sorted_positions.len() as u32;
+
+                self.sorted_positions.push(self.positions[index]);
+                self.sorted_directions.push(self.directions[index]);
+                self.sorted_pixels.push(pixel as u32);
+            }
+        }
+
+        std::mem::swap(&mut self.positions, &mut self.sorted_positions);
+        std::mem::swap(&mut self.directions, &mut self.sorted_directions);
+        std::mem::swap(&mut self.pixels, &mut self.sorted_pixels);
+
+        self.sorted_positions.clear();
+        self.sorted_directions.clear();
+        self.sorted_pixels.clear();
+    }
+
+    pub fn live_cells(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Rebuilds the occupancy grid from the cells' pixels, fails when a pixel is outside the map or taken twice
+    pub(crate) fn rebuild_occupancy(&mut self) -> Result<(), String> {
+        if self.positions.len() != self.directions.len() || self.positions.len() != self.pixels.len() {
+            return Err(String::from("cell arrays differ in length"));
+        }
+
+        let area = check_area(self.width, self.height).map_err(|error| error.to_string())?;
+        self.occupancy = vec![EMPTY; area];
+
+        for (index, pixel) in self.pixels.iter().enumerate() {
+            let pixel = *pixel as usize;
+
+            if pixel >= self.occupancy.len() {
+                return Err(format!("cell at ({}, {}) is outside the environment", pixel % self.width, pixel / self.width));
+            }
+
+            if self.occupancy[pixel] != EMPTY {
+                return Err(format!("two cells at ({}, {})", pixel % self.width, pixel / self.width));
+            }
+
+            self.occupancy[pixel] = index as u32;
+        }
+
+        Ok(())
+    }
+
+    /// Number of other cells in a `window` x `window` square centred on `centre`
+    pub fn neighbours(&self, centre: Point<usize>, window: usize, boundary: BoundaryMode) -> usize {
+        let half = (window / 2) as i64;
+        let (x, y) = (centre.x as i64, centre.y as i64);
+
+        let mut count = 0;
+
+        for j in (y - half)..=(y + half) {
+            for k in (x - half)..=(x + half) {
+                if (k, j) == (x, y) {
+                    continue;
+                }
+
+                if let Some(point) = boundary.resolve(k, j, self.width, self.height) {
+                    if point != centre && self.is_occupied(point) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+
+        count
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Grayscale occupancy image, white where a cell is present
+    pub fn to_grayscale(&self) -> Vec<u8> {
+        let mut data = vec![0u8; self.width * self.height];
+
+        for pixel in self.pixels.iter() {
+            data[*pixel as usize] = 255;
+        }
+
+        data
+    }
+
+    /// RGBA occupancy image, white where a cell is present and black elsewhere
+    pub fn to_rgba(&self) -> Vec<u8> {
+        grayscale_to_rgba(&self.to_grayscale())
+    }
+
+    /// PNG encoded occupancy image
+    pub fn to_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        encode_png_grayscale(self.width, self.height, &self.to_grayscale())
+    }
+
+    #[cfg(feature = "web")]
+    pub fn render(&self) -> ImageData {
+        ImageData::new_with_u8_clamped_array_and_sh(Clamped(&self.to_rgba()), self.width as u32, self.height as u32).unwrap()
+    }
+}
+
+#[cfg(test)]
+pub mod model_tests {
+
+    use super::*;
+
+    #[test]
+    pub fn add_cell()
+    {
+        let mut cell_map = CellMap::new(20, 20, SensorConfig::default()).unwrap();
+        cell_map.add_cell(Point::new(2f64, 4f64), 0f64);
+
+        let res = cell_map.get_cell(Point::new(2, 4));
+
+        assert!(res.is_some());
+    }
+
+    #[test]
+    pub fn occupancy()
+    {
+        let mut cell_map = CellMap::new(4, 4, SensorConfig::default()).unwrap();
+        cell_map.add_cell(Point::new(0f64, 0f64), 0f64);
+        cell_map.add_cell(Point::new(1f64, 2f64), 90f64);
+        cell_map.add_cell(Point::new(3f64, 3f64), 180f64);
+
+        // same pixel replaces, outside the map is ignored
+        cell_map.add_cell(Point::new(1.2f64, 1.8f64), 45f64);
+        cell_map.add_cell(Point::new(3.6f64, 0f64), 0f64);
+        assert_eq!(cell_map.live_cells(), 3);
+        assert_eq!(cell_map.get_cell(Point::new(1, 2)).unwrap().direction, 45f64);
+
+        cell_map.move_cell(0, Point::new(2f64, 0f64), Point::new(2, 0));
+        assert!(!cell_map.is_occupied(Point::new(0, 0)));
+        assert_eq!(cell_map.index_at(Point::new(2, 0)), Some(0));
+
+        // the last cell moves into the freed slot
+        cell_map.remove_cell(0);
+        assert_eq!(cell_map.live_cells(), 2);
+        assert_eq!(cell_map.index_at(Point::new(3, 3)), Some(0));
+        assert_eq!(cell_map.pixel(0), Point::new(3, 3));
+
+        cell_map.remove_cells(&mut [0, 1]);
+        assert_eq!(cell_map.live_cells(), 0);
+        assert!(cell_map.to_grayscale().iter().all(|value| *value == 0));
+
+        cell_map.add_cell(Point::new(3f64, 1f64), 0f64);
+        cell_map.add_cell(Point::new(0f64, 2f64), 90f64);
+        cell_map.add_cell(Point::new(2f64, 0f64), 180f64);
+
+        for _ in 0..2 {
+            cell_map.sort_by_pixel();
+            assert_eq!(cell_map.pixels, vec![2, 7, 8]);
+            assert_eq!(cell_map.directions(), &[180f64, 0f64, 90f64]);
+            assert_eq!(cell_map.index_at(Point::new(0, 2)), Some(2));
+        }
+
+        cell_map.remove_cells(&mut [0, 1, 2]);
+
+        let mut copy = cell_map.clone();
+        copy.pixels.push(20);
+        copy.positions.push(Point::new(0f64, 5f64));
+        copy.directions.push(0f64);
+        assert!(copy.rebuild_occupancy().is_err());
+    }
+
+    #[test]
+    pub fn too_large()
+    {
+        assert!(matches!(CellMap::new(70_000, 70_000, SensorConfig::default()), Err(ConfigError::Invalid(_))));
+        assert!(matches!(CellMap::new(usize::MAX, 2, SensorConfig::default()), Err(ConfigError::Invalid(_))));
+    }
+
+}
\ No newline at end of file
@@ -0,0 +1,546 @@
+//! Simulation parameters, loadable from TOML or JSON files and named presets.
+//!
+//! Fields missing from a file keep their default value, so a file only has to list what it changes:
+//!
+//! ```toml
+//! width = 200
+//! height = 200
+//! decay = 0.1
+//! boundary = "reflective"
+//!
+//! [sensor_config]
+//! angle = 22.5
+//! shape = "circle"
+//!
+//! [diffusion]
+//! kernel = "gaussian"
+//! sigma = 1.5
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::boundary::BoundaryMode;
+use super::trail_map::Concentration;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    /// The file extension is neither `.toml` nor `.json`
+    UnknownFormat(String),
+    UnknownPreset(String),
+    /// A value is out of range, the message names the field
+    Invalid(String)
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(error) => write!(f, "could not read config: {}", error),
+            ConfigError::Toml(error) => write!(f, "could not parse TOML config: {}", error),
+            ConfigError::Json(error) => write!(f, "could not parse JSON config: {}", error),
+            ConfigError::UnknownFormat(path) => write!(f, "config file {} should end in .toml or .json", path),
+            ConfigError::UnknownPreset(name) => write!(f, "unknown preset {}, expected one of: {}", name, PRESETS.join(", ")),
+            ConfigError::Invalid(message) => write!(f, "invalid config: {}", message)
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(error: io::Error) -> Self {
+        ConfigError::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(error: toml::de::Error) -> Self {
+        ConfigError::Toml(error)
+    }
+}
+
+impl From<serde_json::Error> for ConfigError {
+    fn from(error: serde_json::Error) -> Self {
+        ConfigError::Json(error)
+    }
+}
+
+fn check(valid: bool, field: &str, requirement: &str) -> Result<(), ConfigError> {
+    if valid {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(format!("{} {}", field, requirement)))
+    }
+}
+
+fn check_fraction(value: f64, field: &str) -> Result<(), ConfigError> {
+    check((0f64..=1f64).contains(&value), field, "must be between 0 and 1")
+}
+
+fn check_non_negative(value: f64, field: &str) -> Result<(), ConfigError> {
+    check(value.is_finite() && value >= 0f64, field, "must be a finite, non-negative number")
+}
+
+fn check_angle(value: f64, field: &str) -> Result<(), ConfigError> {
+    check(value.is_finite(), field, "must be a finite number of degrees")
+}
+
+/// Largest number of pixels in an environment, agents and their pixels are indexed with a `u32`
+pub const MAX_AREA: usize = u32::MAX as usize - 1;
+
+/// Number of pixels in a `width` x `height` environment, fails when it exceeds `MAX_AREA`
+pub fn check_area(width: usize, height: usize) -> Result<usize, ConfigError> {
+    match width.checked_mul(height) {
+        Some(area) if area <= MAX_AREA => Ok(area),
+        _ => Err(ConfigError::Invalid(format!("width times height must be at most {}", MAX_AREA)))
+    }
+}
+
+/// Shape of the window a sensor averages over
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SensorShape {
+    /// A `width` x `width` square
+    #[default]
+    Square,
+    /// A disc with a diameter of `width`
+    Circle
+}
+
+/// Weights of the neighbourhood mean the trail diffuses towards
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KernelShape {
+    /// Equal weights over a square of `2 * radius + 1` pixels a side
+    #[default]
+    Mean,
+    /// Gaussian weights with a standard deviation of `sigma`
+    Gaussian,
+    /// Gaussian weights spreading `sigma` along the direction `angle` and `sigma_across` across it, with the
+    /// trail carried `drift` along the direction, e.g. to model wind or flow
+    Anisotropic,
+    /// The given `weights`
+    Custom
+}
+
+/// Largest number of pixels a diffusion kernel reaches to any side of a pixel. Validation rejects kernels reaching
+/// further, and kernels built from such settings are cut off at this reach
+pub const MAX_KERNEL_RADIUS: usize = 64;
+
+/// Diffusion of the chemo-attractant, obstacles and pixels the boundary mode places outside of the map are left
+/// out of the mean (see `diffusion`)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiffusionConfig {
+    /// Shape of the kernel, the other fields only apply to some shapes
+    pub kernel: KernelShape,
+    /// Reach of a mean kernel to every side (in pixels), at most `MAX_KERNEL_RADIUS`
+    pub radius: usize,
+    /// Standard deviation of a Gaussian kernel, and along the direction of an anisotropic one (in pixels), a
+    /// Gaussian of 0 leaves the trail as it is
+    pub sigma: f64,
+    /// Standard deviation across the direction of an anisotropic kernel (in pixels)
+    pub sigma_across: f64,
+    /// Direction of an anisotropic kernel (in degrees)
+    pub angle: f64,
+    /// Distance an anisotropic kernel carries the trail along its direction every step (in pixels)
+    pub drift: f64,
+    /// Rows of weights of a custom kernel, an odd number of rows and columns centred on the pixel
+    pub weights: Vec<Vec<f64>>,
+    /// Fraction of each pixel replaced by its diffused value every step (value between 0-1), 0 disables diffusion
+    pub rate: f64
+}
+
+impl Default for DiffusionConfig {
+    fn default() -> Self {
+        Self {
+            kernel: KernelShape::default(),
+            radius: 1,
+            sigma: 1f64,
+            sigma_across: 1f64,
+            angle: 0f64,
+            drift: 0f64,
+            weights: Vec::new(),
+            rate: 1f64
+        }
+    }
+}
+
+impl DiffusionConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        check_fraction(self.rate, "diffusion.rate")?;
+
+        match self.kernel {
+            KernelShape::Mean => {},
+            KernelShape::Gaussian => check_non_negative(self.sigma, "diffusion.sigma")?,
+            KernelShape::Anisotropic => {
+                check(self.sigma.is_finite() && self.sigma > 0f64, "diffusion.sigma", "must be a finite, positive number")?;
+                check(self.sigma_across.is_finite() && self.sigma_across > 0f64, "diffusion.sigma_across", "must be a finite, positive number")?;
+                check_angle(self.angle, "diffusion.angle")?;
+                check_non_negative(self.drift, "diffusion.drift")?
+            },
+            KernelShape::Custom => {
+                let columns = self.weights.first().map(|row| row.len()).unwrap_or(0);
+
+                check(
+                    self.weights.len() % 2 == 1 && columns % 2 == 1 && self.weights.iter().all(|row| row.len() == columns),
+                    "diffusion.weights",
+                    "must have the same odd number of columns in an odd number of rows"
+                )?;
+                check(
+                    self.weights.iter().flatten().all(|weight| weight.is_finite() && *weight >= 0f64),
+                    "diffusion.weights",
+                    "must be finite, non-negative numbers"
+                )?;
+                check(self.weights.iter().flatten().any(|weight| *weight > 0f64), "diffusion.weights", "must not all be 0")?
+            }
+        }
+
+        check(
+            self.reach() <= MAX_KERNEL_RADIUS as f64,
+            "diffusion.kernel",
+            &format!("must not reach further than {} pixels", MAX_KERNEL_RADIUS)
+        )
+    }
+
+    /// Number of pixels the kernel reaches to any side of a pixel
+    pub fn reach(&self) -> f64 {
+        match self.kernel {
+            KernelShape::Mean => self.radius as f64,
+            KernelShape::Gaussian => (3f64 * self.sigma).ceil(),
+            KernelShape::Anisotropic => (3f64 * self.sigma.max(self.sigma_across) + self.drift).ceil(),
+            KernelShape::Custom => {
+                let columns = self.weights.first().map(|row| row.len()).unwrap_or(0);
+
+                (self.weights.len().max(columns) / 2) as f64
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SensorConfig {
+    /// The size of the area that a sensor observes (in pixels)
+    pub width: usize,
+    /// The shape of the area that a sensor observes
+    pub shape: SensorShape,
+    /// The angle between individual sensors
+    pub angle: f64,
+    /// The distance between the agent and the sensor location (in pixels)
+    pub offset_distance: usize
+}
+
+impl Default for SensorConfig {
+    fn default() -> Self {
+        Self {
+            width: 1,
+            shape: SensorShape::default(),
+            angle: 45f64,
+            offset_distance: 9
+        }
+    }
+}
+
+/// Rules for agents dividing and dying during a run
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PopulationConfig {
+    /// Number of steps between population updates, 0 disables division and death
+    pub frequency: usize,
+    /// Size of the square window in which neighbours are counted for division (in pixels)
+    pub birth_window: usize,
+    /// Minimum number of neighbours in the birth window for an agent to divide
+    pub birth_min_neighbours: usize,
+    /// Maximum number of neighbours in the birth window for an agent to divide
+    pub birth_max_neighbours: usize,
+    /// Minimum trail under an agent for it to divide
+    pub birth_min_trail: Concentration,
+    /// Size of the square window in which neighbours are counted for death (in pixels)
+    pub death_window: usize,
+    /// An agent with more neighbours than this in the death window dies of crowding
+    pub death_max_neighbours: usize,
+    /// An agent with less trail than this under it dies of starvation
+    pub death_min_trail: Concentration
+}
+
+impl Default for PopulationConfig {
+    fn default() -> Self {
+        Self {
+            frequency: 0,
+            birth_window: 9,
+            birth_min_neighbours: 0,
+            birth_max_neighbours: 10,
+            birth_min_trail: 0.0,
+            death_window: 5,
+            death_max_neighbours: 24,
+            death_min_trail: 0.0
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    /// Sensor properties
+    pub sensor_config: SensorConfig,
+    /// Distance an agent can move per step (in pixels)
+    pub step_size: usize,
+    /// Chemo-attractant deposition per step
+    pub deposition: Concentration,
+    /// Fraction of chemo-attractant that evaporates each step, before diffusion (value between 0-1)
+    pub decay: f64,
+    /// Fraction of chemo-attractant that evaporates each step, after diffusion (value between 0-1)
+    pub diffusion_decay: f64,
+    /// Kernel and rate the chemo-attractant diffuses with
+    pub diffusion: DiffusionConfig,
+    /// Probability of a random change in direction (value between 0-1)
+    pub cd_prob: f64,
+    /// Sensitivity threshold, sensor readings below this are treated as empty
+    pub s_min: Concentration,
+    /// Width of the environment
+    pub width: usize,
+    /// Height of the environment
+    pub height: usize,
+    /// Behaviour of agents, sensors and diffusion at the edges of the environment
+    pub boundary: BoundaryMode,
+    /// Agent division and death
+    pub population: PopulationConfig,
+    /// How far a cell will rotate when it detects something
+    pub rotation_angle: f64,
+    /// Seed for the simulation's random number generator, equal seeds and configs give identical runs
+    pub seed: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            sensor_config: SensorConfig::default(),
+            step_size: 1,
+            deposition: 255.0,
+            decay: 0f64,
+            diffusion_decay: 0f64,
+            diffusion: DiffusionConfig::default(),
+            cd_prob: 0f64,
+            s_min: 50.0,
+            width: 100,
+            height: 100,
+            boundary: BoundaryMode::default(),
+            population: PopulationConfig::default(),
+            rotation_angle: 45f64,
+            seed: 0
+        }
+    }
+}
+impl SensorConfig {
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(text)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, ConfigError> {
+        let config: Self = serde_json::from_str(text)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        check(self.width > 0, "sensor_config.width", "must be at least 1")?;
+        check_angle(self.angle, "sensor_config.angle")
+    }
+}
+
+impl PopulationConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        check(self.birth_window > 0, "population.birth_window", "must be at least 1")?;
+        check(self.death_window > 0, "population.death_window", "must be at least 1")?;
+        check(
+            self.birth_min_neighbours <= self.birth_max_neighbours,
+            "population.birth_min_neighbours",
+            "must not be greater than population.birth_max_neighbours"
+        )?;
+        check_non_negative(self.birth_min_trail as f64, "population.birth_min_trail")?;
+        check_non_negative(self.death_min_trail as f64, "population.death_min_trail")
+    }
+}
+
+/// Names accepted by `SimulationConfig::preset`
+pub const PRESETS: &[&str] = &["network", "reticular", "labyrinth", "spots", "coarse"];
+
+impl SimulationConfig {
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(text)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, ConfigError> {
+        let config: Self = serde_json::from_str(text)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Reads a `.toml` or `.json` config file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&text),
+            Some("json") => Self::from_json(&text),
+            _ => Err(ConfigError::UnknownFormat(path.display().to_string()))
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        // going through a `Value` puts the plain fields before the sensor and population tables
+        toml::Value::try_from(self)
+            .and_then(|value| toml::to_string(&value))
+            .expect("config always serializes to TOML")
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("config always serializes to JSON")
+    }
+
+    /// Parameter regimes from Jones (2010), "Characteristics of pattern formation and evolution in
+    /// approximations of Physarum transport networks", on a 200 x 200 periodic environment with a
+    /// deposition of 5 and 10% of the trail decaying after diffusion.
+    ///
+    /// - `network`: SA 45, RA 45, SO 9, the baseline quasi-stable transport network
+    /// - `reticular`: SA 22.5, RA 45, SO 9, rotation wider than the sensors gives fine, constantly changing reticular networks
+    /// - `labyrinth`: SA 90, RA 45, SO 9, wide sensors give labyrinthine patterns
+    /// - `spots`: SA 45, RA 22.5, SO 9, rotation narrower than the sensors condenses the trail into spots
+    /// - `coarse`: SA 45, RA 45, SO 23, a longer sensor offset scales the network up
+    pub fn preset(name: &str) -> Result<Self, ConfigError> {
+        let (angle, rotation_angle, offset_distance) = match name {
+            "network" => (45f64, 45f64, 9),
+            "reticular" => (22.5f64, 45f64, 9),
+            "labyrinth" => (90f64, 45f64, 9),
+            "spots" => (45f64, 22.5f64, 9),
+            "coarse" => (45f64, 45f64, 23),
+            _ => return Err(ConfigError::UnknownPreset(String::from(name)))
+        };
+
+        Ok(Self {
+            sensor_config: SensorConfig { width: 1, shape: SensorShape::Square, angle, offset_distance },
+            step_size: 1,
+            deposition: 5.0,
+            decay: 0f64,
+            diffusion_decay: 0.1f64,
+            diffusion: DiffusionConfig::default(),
+            cd_prob: 0f64,
+            s_min: 0.0,
+            width: 200,
+            height: 200,
+            boundary: BoundaryMode::Periodic,
+            population: PopulationConfig::default(),
+            rotation_angle,
+            seed: 0
+        })
+    }
+
+    /// Checks that every parameter is in range, e.g. probabilities between 0 and 1 and a non-empty environment
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        check(self.width > 0, "width", "must be at least 1")?;
+        check(self.height > 0, "height", "must be at least 1")?;
+        check_area(self.width, self.height)?;
+        check(self.step_size > 0, "step_size", "must be at least 1")?;
+        check_non_negative(self.deposition as f64, "deposition")?;
+        check_fraction(self.decay, "decay")?;
+        check_fraction(self.diffusion_decay, "diffusion_decay")?;
+        check_fraction(self.cd_prob, "cd_prob")?;
+        check_non_negative(self.s_min as f64, "s_min")?;
+        check_angle(self.rotation_angle, "rotation_angle")?;
+
+        self.sensor_config.validate()?;
+        self.population.validate()?;
+        self.diffusion.validate()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    #[test]
+    fn partial_toml()
+    {
+        let config = SimulationConfig::from_toml(r#"
+            width = 64
+            cd_prob = 0.05
+            boundary = "reflective"
+
+            [sensor_config]
+            angle = 22.5
+            shape = "circle"
+
+            [diffusion]
+            kernel = "custom"
+            weights = [[0, 1, 0], [1, 4, 1], [0, 1, 0]]
+            rate = 0.5
+        "#).unwrap();
+
+        assert_eq!(config.width, 64);
+        assert_eq!(config.height, SimulationConfig::default().height);
+        assert_eq!(config.boundary, BoundaryMode::Reflective);
+        assert_eq!(config.sensor_config.shape, SensorShape::Circle);
+        assert_eq!(config.sensor_config.offset_distance, 9);
+        assert_eq!(config.diffusion.kernel, KernelShape::Custom);
+        assert_eq!(config.diffusion.weights[1], vec![1.0, 4.0, 1.0]);
+        assert_eq!(config.diffusion.radius, 1);
+    }
+
+    #[test]
+    fn json_round_trip()
+    {
+        let mut config = SimulationConfig::preset("labyrinth").unwrap();
+
+        assert_eq!(SimulationConfig::from_json(&config.to_json()).unwrap(), config);
+        assert_eq!(SimulationConfig::from_toml(&config.to_toml()).unwrap(), config);
+
+        config.diffusion = DiffusionConfig { kernel: KernelShape::Anisotropic, sigma: 2.0, angle: 90.0, drift: 1.5, ..DiffusionConfig::default() };
+        assert_eq!(SimulationConfig::from_toml(&config.to_toml()).unwrap(), config);
+        assert_eq!(SensorConfig::from_json(r#"{ "width": 3 }"#).unwrap().width, 3);
+    }
+
+    #[test]
+    fn validation()
+    {
+        let invalid = |text: &str| match SimulationConfig::from_toml(text) {
+            Err(ConfigError::Invalid(message)) => message,
+            other => panic!("expected a validation error, got {:?}", other)
+        };
+
+        assert!(invalid("cd_prob = 1.5").starts_with("cd_prob"));
+        assert!(invalid("width = 0").starts_with("width"));
+        assert!(invalid("width = 70000\nheight = 70000").starts_with("width times height"));
+        assert!(invalid("[sensor_config]\nwidth = 0").starts_with("sensor_config.width"));
+        assert!(invalid("[diffusion]\nrate = 2").starts_with("diffusion.rate"));
+        assert!(invalid("[diffusion]\nkernel = \"custom\"\nweights = [[1, 1]]").starts_with("diffusion.weights"));
+        assert!(invalid("[diffusion]\nkernel = \"anisotropic\"\nsigma_across = 0").starts_with("diffusion.sigma_across"));
+        assert!(invalid("[diffusion]\nradius = 1000000").starts_with("diffusion.kernel"));
+        assert!(invalid("[diffusion]\nkernel = \"gaussian\"\nsigma = 1e300").starts_with("diffusion.kernel"));
+        assert!(invalid("[diffusion]\nkernel = \"anisotropic\"\ndrift = 100").starts_with("diffusion.kernel"));
+        assert!(SimulationConfig::from_toml("[diffusion]\nradius = 64").is_ok());
+        assert!(matches!(SimulationConfig::from_toml("decay = \"lots\""), Err(ConfigError::Toml(_))));
+        assert!(matches!(SimulationConfig::preset("mould"), Err(ConfigError::UnknownPreset(_))));
+
+        for name in PRESETS {
+            assert!(SimulationConfig::preset(name).unwrap().validate().is_ok());
+        }
+    }
+}
@@ -0,0 +1,569 @@
+//! Diffusion of the chemoattractant: every pixel becomes the weighted mean of its neighbourhood. Mean and
+//! Gaussian kernels are separable and computed as a horizontal pass followed by a vertical pass, so the cost per
+//! pixel grows with the kernel's width rather than its area. Anisotropic and custom kernels go through a direct
+//! 2D pass.
+//!
+//! Obstacles take no part in the mean, so both passes filter the masked values and the weights of the pixels that
+//! are not obstacles, and each pixel is the ratio of the two. Taps that fall outside the map follow the boundary
+//! mode: periodic and reflective edges read the pixel they map to, absorbing edges count as empty pixels and
+//! clamped edges crop the kernel.
+
+use super::boundary::*;
+use super::config::*;
+use super::trail_map::*;
+
+/// A 2D kernel that is the product of a horizontal and a vertical 1D kernel, both of odd length and centred
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeparableKernel {
+    pub horizontal: Vec<Concentration>,
+    pub vertical: Vec<Concentration>
+}
+
+impl SeparableKernel {
+    /// Uniform weights over a square of `2 * radius + 1` pixels a side, with `radius` cut off at `MAX_KERNEL_RADIUS`
+    pub fn mean(radius: usize) -> Self {
+        let weights = vec![1 as Concentration; 2 * radius.min(MAX_KERNEL_RADIUS) + 1];
+
+        Self { horizontal: weights.clone(), vertical: weights }
+    }
+
+    /// Gaussian weights with the given standard deviation (in pixels), cut off at three standard deviations
+    pub fn gaussian(sigma: f64) -> Self {
+        let weights = gaussian_weights(sigma);
+
+        Self { horizontal: weights.clone(), vertical: weights }
+    }
+
+    /// Number of pixels the kernel reaches to either side horizontally
+    pub fn radius_x(&self) -> usize {
+        self.horizontal.len() / 2
+    }
+
+    /// Number of pixels the kernel reaches up and down
+    pub fn radius_y(&self) -> usize {
+        self.vertical.len() / 2
+    }
+}
+
+/// Standard deviation (in pixels) anisotropic kernels fall back to for a spread of 0, narrow enough for the
+/// neighbouring pixels to weigh next to nothing
+const MIN_SIGMA: f64 = 0.25f64;
+
+/// A 2D kernel with a weight per pixel, row-major with an odd number of rows and columns and centred
+#[derive(Clone, Debug, PartialEq)]
+pub struct DenseKernel {
+    pub width: usize,
+    pub height: usize,
+    pub weights: Vec<Concentration>
+}
+
+impl DenseKernel {
+    /// Kernel from rows of weights, see `KernelShape::Custom`. Negative and non-finite weights count as 0, and rows
+    /// that do not form a centred kernel (or weigh nothing at all) leave the trail as it is
+    pub fn from_rows(rows: &[Vec<f64>]) -> Self {
+        let width = rows.first().map(|row| row.len()).unwrap_or(0);
+        let weights: Vec<Concentration> = rows.iter().flatten()
+            .map(|weight| if weight.is_finite() && *weight > 0f64 { *weight as Concentration } else { 0 as Concentration })
+            .collect();
+
+        let centred = width % 2 == 1 && rows.len() % 2 == 1 && rows.iter().all(|row| row.len() == width);
+
+        if !centred || weights.iter().all(|weight| *weight == 0 as Concentration) {
+            return Self::identity();
+        }
+
+        Self { width, height: rows.len(), weights }
+    }
+
+    /// Gaussian stretched along the direction `angle` (in degrees) and centred `drift` pixels against it, so
+    /// every pixel gathers from upstream and the trail moves downstream. See `KernelShape::Anisotropic`. A
+    /// standard deviation of 0 (or one that is not a finite number) does not spread the trail along its axis
+    pub fn anisotropic(sigma_along: f64, sigma_across: f64, angle: f64, drift: f64) -> Self {
+        let spread = |sigma: f64| if sigma.is_finite() && sigma > 0f64 { sigma } else { MIN_SIGMA };
+        let (sigma_along, sigma_across) = (spread(sigma_along), spread(sigma_across));
+        let angle = if angle.is_finite() { angle } else { 0f64 };
+        let drift = if drift.is_finite() { drift.abs() } else { 0f64 };
+
+        let radius = (3f64 * sigma_along.max(sigma_across) + drift).ceil().min(MAX_KERNEL_RADIUS as f64) as i64;
+        let (sin, cos) = angle.to_radians().sin_cos();
+
+        let weights = (-radius..=radius).flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+            .map(|(dx, dy)| {
+                let (x, y) = (dx as f64 + drift * cos, dy as f64 + drift * sin);
+                let (along, across) = (x * cos + y * sin, y * cos - x * sin);
+
+                (-along * along / (2f64 * sigma_along * sigma_along) - across * across / (2f64 * sigma_across * sigma_across)).exp() as Concentration
+            })
+            .collect();
+
+        let side = 2 * radius as usize + 1;
+
+        Self { width: side, height: side, weights }
+    }
+
+    /// A single weight, the mean of a pixel is the pixel itself
+    pub fn identity() -> Self {
+        Self { width: 1, height: 1, weights: vec![1 as Concentration] }
+    }
+}
+
+/// Weights of the neighbourhood mean
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kernel {
+    Separable(SeparableKernel),
+    Dense(DenseKernel)
+}
+
+impl Kernel {
+    pub fn new(config: &DiffusionConfig) -> Self {
+        match config.kernel {
+            KernelShape::Mean => Kernel::Separable(SeparableKernel::mean(config.radius)),
+            KernelShape::Gaussian => Kernel::Separable(SeparableKernel::gaussian(config.sigma)),
+            KernelShape::Anisotropic => Kernel::Dense(DenseKernel::anisotropic(config.sigma, config.sigma_across, config.angle, config.drift)),
+            KernelShape::Custom => Kernel::Dense(DenseKernel::from_rows(&config.weights))
+        }
+    }
+}
+
+/// 1D Gaussian weights cut off at three standard deviations (or `MAX_KERNEL_RADIUS`), a single weight for
+/// `sigma <= 0` or a `sigma` that is not a finite number
+pub fn gaussian_weights(sigma: f64) -> Vec<Concentration> {
+    if !sigma.is_finite() || sigma <= 0f64 {
+        return vec![1 as Concentration];
+    }
+
+    let radius = (3f64 * sigma).ceil().min(MAX_KERNEL_RADIUS as f64) as i64;
+
+    (-radius..=radius)
+        .map(|offset| (-(offset * offset) as f64 / (2f64 * sigma * sigma)).exp() as Concentration)
+        .collect()
+}
+
+/// Scratch space kept between steps so diffusion does not allocate. Cloning gives empty buffers, they are
+/// resized on the next use
+#[derive(Debug, Default)]
+pub struct DiffusionBuffers {
+    /// Receives the diffused map and is swapped with the trail map's data
+    back: Vec<Concentration>,
+    /// Horizontal pass of the masked values
+    values: Vec<Concentration>,
+    /// Horizontal pass of the weights
+    weights: Vec<Concentration>
+}
+
+impl Clone for DiffusionBuffers {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// Where a kernel tap lands
+enum Tap {
+    /// On a pixel of the map, along the axis
+    Pixel(usize),
+    /// Outside the map on an edge that counts as empty
+    Empty,
+    /// Outside the map on an edge that crops the kernel
+    Cropped
+}
+
+fn tap(position: i64, size: usize, boundary: BoundaryMode) -> Tap {
+    if position >= 0 && position < size as i64 {
+        return Tap::Pixel(position as usize);
+    }
+
+    match boundary {
+        BoundaryMode::Clamped => Tap::Cropped,
+        BoundaryMode::Absorbing => Tap::Empty,
+        _ => boundary.resolve(position, 0, size, 1).map(|point| Tap::Pixel(point.x)).unwrap_or(Tap::Empty)
+    }
+}
+
+/// Moves `data` (a `width` x `height` row-major map) a fraction `rate` (between 0-1) of the way towards its
+/// weighted neighbourhood mean. Obstacles end up empty and neither give nor take chemoattractant
+#[allow(clippy::too_many_arguments)]
+pub fn diffuse<F: Fn(usize) -> bool>(
+    data: &mut Vec<Concentration>,
+    buffers: &mut DiffusionBuffers,
+    width: usize,
+    height: usize,
+    kernel: &Kernel,
+    rate: Concentration,
+    boundary: BoundaryMode,
+    is_obstacle: F
+) {
+    let n = width * height;
+    assert_eq!(data.len(), n, "data does not match dimensions");
+
+    if rate.is_nan() || rate <= 0 as Concentration {
+        return;
+    }
+
+    let mix = |original: Concentration, mean: Concentration| {
+        if rate >= 1 as Concentration { mean } else { original + rate * (mean - original) }
+    };
+
+    buffers.back.resize(n, 0 as Concentration);
+
+    match kernel {
+        Kernel::Separable(kernel) => separable(data, buffers, width, height, kernel, boundary, is_obstacle, mix),
+        Kernel::Dense(kernel) => dense(data, &mut buffers.back, width, height, kernel, boundary, is_obstacle, mix)
+    }
+
+    std::mem::swap(data, &mut buffers.back);
+}
+
+/// Horizontal then vertical pass, writing into `buffers.back`
+#[allow(clippy::too_many_arguments)]
+fn separable<F: Fn(usize) -> bool, M: Fn(Concentration, Concentration) -> Concentration>(
+    data: &[Concentration],
+    buffers: &mut DiffusionBuffers,
+    width: usize,
+    height: usize,
+    kernel: &SeparableKernel,
+    boundary: BoundaryMode,
+    is_obstacle: F,
+    mix: M
+) {
+    let n = width * height;
+
+    buffers.values.resize(n, 0 as Concentration);
+    buffers.weights.resize(n, 0 as Concentration);
+
+    let (rx, ry) = (kernel.radius_x(), kernel.radius_y());
+
+    // horizontal pass, edges only need resolving within `rx` of the sides
+    for y in 0..height {
+        let row = y * width;
+
+        for x in 0..width {
+            let (mut value, mut weight) = (0 as Concentration, 0 as Concentration);
+
+            if x >= rx && x + rx < width {
+                for (t, w) in kernel.horizontal.iter().enumerate() {
+                    let i = row + x + t - rx;
+
+                    if !is_obstacle(i) {
+                        value += w * data[i];
+                        weight += w;
+                    }
+                }
+            } else {
+                for (t, w) in kernel.horizontal.iter().enumerate() {
+                    match tap(x as i64 + t as i64 - rx as i64, width, boundary) {
+                        Tap::Pixel(k) => {
+                            if !is_obstacle(row + k) {
+                                value += w * data[row + k];
+                                weight += w;
+                            }
+                        },
+                        Tap::Empty => weight += w,
+                        Tap::Cropped => {}
+                    }
+                }
+            }
+
+            buffers.values[row + x] = value;
+            buffers.weights[row + x] = weight;
+        }
+    }
+
+    // a row outside an absorbing edge weighs as much as the whole horizontal kernel
+    let empty_row: Concentration = kernel.horizontal.iter().sum();
+
+    // vertical pass, going along rows so every tap reads a contiguous row of the horizontal pass
+    for y in 0..height {
+        let row = y * width;
+        let interior = y >= ry && y + ry < height;
+
+        for x in 0..width {
+            let i = row + x;
+
+            if is_obstacle(i) {
+                buffers.back[i] = 0 as Concentration;
+                continue;
+            }
+
+            let (mut value, mut weight) = (0 as Concentration, 0 as Concentration);
+
+            for (t, w) in kernel.vertical.iter().enumerate() {
+                let source = if interior {
+                    Tap::Pixel(y + t - ry)
+                } else {
+                    tap(y as i64 + t as i64 - ry as i64, height, boundary)
+                };
+
+                match source {
+                    Tap::Pixel(j) => {
+                        value += w * buffers.values[j * width + x];
+                        weight += w * buffers.weights[j * width + x];
+                    },
+                    Tap::Empty => weight += w * empty_row,
+                    Tap::Cropped => {}
+                }
+            }
+
+            buffers.back[i] = mix(data[i], if weight > 0 as Concentration { value / weight } else { 0 as Concentration });
+        }
+    }
+}
+
+/// Direct 2D pass over every weight of the kernel, writing into `back`
+#[allow(clippy::too_many_arguments)]
+fn dense<F: Fn(usize) -> bool, M: Fn(Concentration, Concentration) -> Concentration>(
+    data: &[Concentration],
+    back: &mut [Concentration],
+    width: usize,
+    height: usize,
+    kernel: &DenseKernel,
+    boundary: BoundaryMode,
+    is_obstacle: F,
+    mix: M
+) {
+    let (rx, ry) = (kernel.width / 2, kernel.height / 2);
+
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+
+            if is_obstacle(i) {
+                back[i] = 0 as Concentration;
+                continue;
+            }
+
+            let interior = x >= rx && x + rx < width && y >= ry && y + ry < height;
+            let (mut value, mut weight) = (0 as Concentration, 0 as Concentration);
+
+            for (t, w) in kernel.weights.iter().enumerate() {
+                let (tx, ty) = (t % kernel.width, t / kernel.width);
+
+                let source = if interior {
+                    (Tap::Pixel(x + tx - rx), Tap::Pixel(y + ty - ry))
+                } else {
+                    (
+                        tap(x as i64 + tx as i64 - rx as i64, width, boundary),
+                        tap(y as i64 + ty as i64 - ry as i64, height, boundary)
+                    )
+                };
+
+                match source {
+                    (Tap::Pixel(k), Tap::Pixel(j)) => {
+                        if !is_obstacle(j * width + k) {
+                            value += w * data[j * width + k];
+                            weight += w;
+                        }
+                    },
+                    (Tap::Cropped, _) | (_, Tap::Cropped) => {},
+                    _ => weight += w
+                }
+            }
+
+            back[i] = mix(data[i], if weight > 0 as Concentration { value / weight } else { 0 as Concentration });
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    /// Rows of weights of any kernel
+    fn rows(kernel: &Kernel) -> Vec<Vec<f64>> {
+        match kernel {
+            Kernel::Separable(kernel) => kernel.vertical.iter()
+                .map(|v| kernel.horizontal.iter().map(|h| (h * v) as f64).collect())
+                .collect(),
+            Kernel::Dense(kernel) => kernel.weights.chunks(kernel.width)
+                .map(|row| row.iter().map(|weight| *weight as f64).collect())
+                .collect()
+        }
+    }
+
+    /// Direct 2D weighted mean of every pixel, the definition both passes must agree with
+    fn reference(data: &[Concentration], width: usize, height: usize, kernel: &Kernel, boundary: BoundaryMode, obstacles: &[bool]) -> Vec<Concentration> {
+        let weights = rows(kernel);
+        let (rx, ry) = ((weights[0].len() / 2) as i64, (weights.len() / 2) as i64);
+
+        (0..width * height).map(|i| {
+            if obstacles[i] {
+                return 0.0;
+            }
+
+            let (x, y) = ((i % width) as i64, (i / width) as i64);
+            let (mut sum, mut n) = (0f64, 0f64);
+
+            for dy in -ry..=ry {
+                for dx in -rx..=rx {
+                    let w = weights[(dy + ry) as usize][(dx + rx) as usize];
+                    let (k, j) = (x + dx, y + dy);
+                    let inside = k >= 0 && j >= 0 && k < width as i64 && j < height as i64;
+
+                    if !inside && boundary == BoundaryMode::Clamped {
+                        continue;
+                    }
+
+                    match boundary.resolve(k, j, width, height) {
+                        Some(point) if inside || boundary != BoundaryMode::Absorbing => {
+                            let index = point.y * width + point.x;
+
+                            if !obstacles[index] {
+                                sum += w * data[index] as f64;
+                                n += w;
+                            }
+                        },
+                        _ => n += w
+                    }
+                }
+            }
+
+            (sum / n) as Concentration
+        }).collect()
+    }
+
+    #[test]
+    fn matches_reference()
+    {
+        let (width, height) = (9, 7);
+        let data: Vec<Concentration> = (0..width * height).map(|i| ((i * 37) % 23) as Concentration).collect();
+        let obstacles: Vec<bool> = (0..width * height).map(|i| i % 11 == 3).collect();
+
+        let kernels = [
+            Kernel::Separable(SeparableKernel::mean(1)),
+            Kernel::Separable(SeparableKernel::mean(4)),
+            Kernel::Separable(SeparableKernel::gaussian(1.5)),
+            Kernel::Separable(SeparableKernel { horizontal: vec![1.0, 2.0, 1.0], vertical: vec![1.0] }),
+            Kernel::Dense(DenseKernel::from_rows(&[vec![0.0, 1.0, 0.0], vec![2.0, 4.0, 2.0], vec![0.0, 1.0, 3.0]])),
+            Kernel::Dense(DenseKernel::anisotropic(2.0, 0.5, 30.0, 1.5))
+        ];
+
+        for kernel in kernels.iter() {
+            for boundary in [BoundaryMode::Periodic, BoundaryMode::Reflective, BoundaryMode::Clamped, BoundaryMode::Absorbing] {
+                let mut diffused = data.clone();
+                diffuse(&mut diffused, &mut DiffusionBuffers::default(), width, height, kernel, 1.0, boundary, |i| obstacles[i]);
+
+                let expected = reference(&data, width, height, kernel, boundary, &obstacles);
+
+                for (a, b) in diffused.iter().zip(expected.iter()) {
+                    assert!((a - b).abs() < 1e-3, "{:?} {:?}: {} != {}", kernel, boundary, a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn buffers_are_reused()
+    {
+        let mut data = vec![1 as Concentration; 16 * 16];
+        let mut buffers = DiffusionBuffers::default();
+        let kernel = Kernel::Separable(SeparableKernel::gaussian(1.0));
+
+        diffuse(&mut data, &mut buffers, 16, 16, &kernel, 1.0, BoundaryMode::Periodic, |_| false);
+        let pointers = (data.as_ptr(), buffers.back.as_ptr());
+
+        // the two buffers trade places every step
+        diffuse(&mut data, &mut buffers, 16, 16, &kernel, 1.0, BoundaryMode::Periodic, |_| false);
+        assert_eq!((buffers.back.as_ptr(), data.as_ptr()), pointers);
+
+        diffuse(&mut data, &mut buffers, 16, 16, &kernel, 1.0, BoundaryMode::Periodic, |_| false);
+        assert_eq!((data.as_ptr(), buffers.back.as_ptr()), pointers);
+
+        // a uniform map stays uniform on a periodic map
+        assert!(data.iter().all(|value| (value - 1.0).abs() < 1e-5));
+    }
+
+    #[test]
+    fn gaussian()
+    {
+        let weights = gaussian_weights(2.0);
+        assert_eq!(weights.len(), 13);
+        assert_eq!(weights[6], 1.0);
+        assert_eq!(weights[0], weights[12]);
+        assert!(weights[5] < weights[6] && weights[4] < weights[5]);
+
+        assert_eq!(gaussian_weights(0.0), vec![1.0]);
+        assert_eq!(SeparableKernel::mean(2).radius_x(), 2);
+        assert_eq!(Kernel::new(&DiffusionConfig::default()), Kernel::Separable(SeparableKernel::mean(1)));
+    }
+
+    #[test]
+    fn invalid_settings()
+    {
+        // settings `DiffusionConfig::validate` rejects still give a usable kernel
+        let custom = |weights: Vec<Vec<f64>>| Kernel::new(&DiffusionConfig { kernel: KernelShape::Custom, weights, ..DiffusionConfig::default() });
+        assert_eq!(custom(Vec::new()), Kernel::Dense(DenseKernel::identity()));
+        assert_eq!(custom(vec![vec![1.0, 1.0]]), Kernel::Dense(DenseKernel::identity()));
+        assert_eq!(custom(vec![vec![-1.0, f64::NAN, 0.0]]), Kernel::Dense(DenseKernel::identity()));
+        assert_eq!(DenseKernel::from_rows(&[vec![-1.0, f64::NAN, 2.0]]).weights, vec![0.0, 0.0, 2.0]);
+
+        assert_eq!(gaussian_weights(f64::NAN), vec![1.0]);
+        assert_eq!(gaussian_weights(f64::INFINITY), vec![1.0]);
+        assert_eq!(gaussian_weights(1e300).len(), 2 * MAX_KERNEL_RADIUS + 1);
+        assert_eq!(SeparableKernel::mean(usize::MAX).radius_x(), MAX_KERNEL_RADIUS);
+        assert_eq!(DenseKernel::anisotropic(1.0, 1.0, 0.0, 1e12).width, 2 * MAX_KERNEL_RADIUS + 1);
+
+        let mut data: Vec<Concentration> = (0..64).map(|i| i as Concentration).collect();
+        let mut buffers = DiffusionBuffers::default();
+
+        for (sigma, sigma_across) in [(0.0, 0.0), (-1.0, 1.0), (f64::NAN, 2.0)] {
+            let kernel = Kernel::new(&DiffusionConfig { kernel: KernelShape::Anisotropic, sigma, sigma_across, drift: f64::NAN, ..DiffusionConfig::default() });
+            diffuse(&mut data, &mut buffers, 8, 8, &kernel, 1.0, BoundaryMode::Periodic, |_| false);
+            assert!(data.iter().all(|value| value.is_finite()));
+        }
+
+        diffuse(&mut data, &mut buffers, 8, 8, &Kernel::Dense(DenseKernel::identity()), Concentration::NAN, BoundaryMode::Periodic, |_| false);
+        assert!(data.iter().all(|value| value.is_finite()));
+    }
+
+    #[test]
+    fn drift()
+    {
+        let (width, height) = (32, 32);
+        let mut data = vec![0 as Concentration; width * height];
+        data[16 * width + 10] = 100.0;
+
+        let centroid = |data: &[Concentration]| {
+            let total: f64 = data.iter().map(|value| *value as f64).sum();
+            let x: f64 = data.iter().enumerate().map(|(i, value)| (i % width) as f64 * *value as f64).sum();
+            let y: f64 = data.iter().enumerate().map(|(i, value)| (i / width) as f64 * *value as f64).sum();
+            (x / total, y / total)
+        };
+
+        // flowing along +x, the trail moves downstream and spreads more along the flow than across it
+        let kernel = Kernel::new(&DiffusionConfig { kernel: KernelShape::Anisotropic, sigma: 1.5, sigma_across: 0.5, drift: 3.0, ..DiffusionConfig::default() });
+        let mut flowed = data.clone();
+        diffuse(&mut flowed, &mut DiffusionBuffers::default(), width, height, &kernel, 1.0, BoundaryMode::Periodic, |_| false);
+
+        let (x, y) = centroid(&flowed);
+        assert!((x - 13.0).abs() < 0.1 && (y - 16.0).abs() < 1e-6, "centroid at {}, {}", x, y);
+        assert!(flowed[16 * width + 15] > flowed[18 * width + 13]);
+
+        // a custom kernel reading only from the right neighbour shifts everything left
+        let kernel = Kernel::new(&DiffusionConfig {
+            kernel: KernelShape::Custom,
+            weights: vec![vec![0.0, 0.0, 0.0], vec![0.0, 0.0, 1.0], vec![0.0, 0.0, 0.0]],
+            ..DiffusionConfig::default()
+        });
+        let mut shifted = data.clone();
+        diffuse(&mut shifted, &mut DiffusionBuffers::default(), width, height, &kernel, 1.0, BoundaryMode::Periodic, |_| false);
+        assert_eq!(shifted[16 * width + 9], 100.0);
+    }
+
+    #[test]
+    fn rate()
+    {
+        let kernel = Kernel::Separable(SeparableKernel::mean(1));
+        let mut buffers = DiffusionBuffers::default();
+
+        let mut data = vec![0.0, 90.0, 0.0];
+        diffuse(&mut data, &mut buffers, 3, 1, &kernel, 0.5, BoundaryMode::Clamped, |_| false);
+        assert_eq!(data, vec![22.5, 60.0, 22.5]);
+
+        // nothing diffuses at a rate of 0, and obstacles stay empty at any other rate
+        diffuse(&mut data, &mut buffers, 3, 1, &kernel, 0.0, BoundaryMode::Clamped, |_| false);
+        assert_eq!(data, vec![22.5, 60.0, 22.5]);
+
+        diffuse(&mut data, &mut buffers, 3, 1, &kernel, 0.5, BoundaryMode::Clamped, |i| i == 2);
+        assert_eq!(data[2], 0.0);
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use super::boundary::*;
+use super::config::*;
+use super::plane::*;
+use super::point::*;
+use super::trail_map::*;
+
+/// Static properties of a single pixel of the environment
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Terrain {
+    /// Agents cannot enter obstacles and chemoattractant does not diffuse through them
+    pub obstacle: bool,
+    /// Strength of the repellent at this pixel, subtracted from what sensors read
+    pub repellent: Concentration
+}
+
+impl Terrain {
+    pub fn obstacle() -> Self {
+        Self { obstacle: true, repellent: 0.0 }
+    }
+
+    pub fn repellent(strength: Concentration) -> Self {
+        Self { obstacle: false, repellent: strength }
+    }
+}
+
+/// Layer of obstacles and repellents next to the trail map
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Environment {
+    pub data: Vec<Terrain>,
+    width: usize,
+    height: usize
+}
+
+impl Environment {
+    /// An open environment without obstacles or repellents
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            data: vec![Terrain::default(); width * height],
+            width,
+            height
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, terrain: Terrain) {
+        let index = self.get_index(x, y);
+        self.data[index] = terrain;
+    }
+
+    /// Sets every pixel of a rectangle (clipped to the environment) to the given terrain
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, terrain: Terrain) {
+        for j in y..(y + height).min(self.height) {
+            for k in x..(x + width).min(self.width) {
+                self.set(k, j, terrain);
+            }
+        }
+    }
+
+    pub fn is_obstacle(&self, point: Point<usize>) -> bool {
+        self.get_value(point.x, point.y).map(|terrain| terrain.obstacle).unwrap_or(false)
+    }
+
+    pub fn has_obstacles(&self) -> bool {
+        self.data.iter().any(|terrain| terrain.obstacle)
+    }
+
+    /// Average repellent strength in a sensor window centred on `point`
+    pub fn sample_repellent(&self, point: Point<f64>, width: usize, shape: SensorShape, boundary: BoundaryMode) -> Concentration {
+        self.sample_with(point, width, shape, boundary, |terrain| terrain.repellent as f64) as Concentration
+    }
+}
+
+impl Plane<Terrain> for Environment {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn data(&self) -> &Vec<Terrain> {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    #[test]
+    fn fill_rect()
+    {
+        let mut environment = Environment::new(4, 4);
+        environment.fill_rect(2, 1, 5, 2, Terrain::obstacle());
+
+        assert!(environment.is_obstacle(Point::new(3, 2)));
+        assert!(!environment.is_obstacle(Point::new(1, 1)));
+        assert_eq!(environment.data.iter().filter(|terrain| terrain.obstacle).count(), 4);
+    }
+}
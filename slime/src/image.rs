@@ -0,0 +1,125 @@
+//! Image encoders for writing maps to disk without a browser.
+
+/// Encodes 8-bit grayscale pixels (row-major, one byte per pixel) as a binary PGM (P5) image
+pub fn encode_pgm(width: usize, height: usize, pixels: &[u8]) -> Vec<u8> {
+    assert_eq!(pixels.len(), width * height, "pixel buffer does not match image dimensions");
+
+    let mut bytes = format!("P5\n{} {}\n255\n", width, height).into_bytes();
+    bytes.extend_from_slice(pixels);
+
+    bytes
+}
+
+fn encode_png(width: usize, height: usize, pixels: &[u8], color: png::ColorType) -> Result<Vec<u8>, png::EncodingError> {
+    assert_eq!(pixels.len(), width * height * color.samples(), "pixel buffer does not match image dimensions");
+
+    let mut bytes = Vec::new();
+
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+        encoder.set_color(color);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(pixels)?;
+    }
+
+    Ok(bytes)
+}
+
+/// Encodes 8-bit grayscale pixels (row-major, one byte per pixel) as a PNG image
+pub fn encode_png_grayscale(width: usize, height: usize, pixels: &[u8]) -> Result<Vec<u8>, png::EncodingError> {
+    encode_png(width, height, pixels, png::ColorType::Grayscale)
+}
+
+/// Encodes 8-bit RGBA pixels (row-major, four bytes per pixel) as a PNG image
+pub fn encode_png_rgba(width: usize, height: usize, pixels: &[u8]) -> Result<Vec<u8>, png::EncodingError> {
+    encode_png(width, height, pixels, png::ColorType::Rgba)
+}
+
+/// Expands grayscale pixels to opaque RGBA
+pub fn grayscale_to_rgba(pixels: &[u8]) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(pixels.len() * 4);
+
+    for value in pixels.iter() {
+        rgba.extend_from_slice(&[*value, *value, *value, 255]);
+    }
+
+    rgba
+}
+
+/// Shrinks grayscale pixels so neither side exceeds `max_size`, averaging each block of source pixels.
+/// Returns the new width, height and pixels, images that already fit are returned unchanged
+pub fn downscale_grayscale(width: usize, height: usize, pixels: &[u8], max_size: usize) -> (usize, usize, Vec<u8>) {
+    assert_eq!(pixels.len(), width * height, "pixel buffer does not match image dimensions");
+
+    let max_size = max_size.max(1);
+    let factor = width.max(height).div_ceil(max_size).max(1);
+
+    if factor == 1 {
+        return (width, height, pixels.to_vec());
+    }
+
+    let (new_width, new_height) = (width.div_ceil(factor), height.div_ceil(factor));
+    let mut scaled = Vec::with_capacity(new_width * new_height);
+
+    for y in 0..new_height {
+        for x in 0..new_width {
+            let (mut sum, mut n) = (0u32, 0u32);
+
+            for j in (y * factor)..((y + 1) * factor).min(height) {
+                for k in (x * factor)..((x + 1) * factor).min(width) {
+                    sum += pixels[j * width + k] as u32;
+                    n += 1;
+                }
+            }
+
+            scaled.push((sum as f64 / n as f64).round() as u8);
+        }
+    }
+
+    (new_width, new_height, scaled)
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    #[test]
+    fn pgm_header()
+    {
+        let bytes = encode_pgm(2, 1, &[0, 255]);
+
+        assert_eq!(bytes, b"P5\n2 1\n255\n\x00\xff".to_vec());
+    }
+
+    #[test]
+    fn png_round_trip()
+    {
+        let pixels = grayscale_to_rgba(&[0, 128, 255]);
+        let bytes = encode_png_rgba(3, 1, &pixels).unwrap();
+
+        let decoder = png::Decoder::new(bytes.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buffer = vec![0u8; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).unwrap();
+
+        assert_eq!((info.width, info.height, info.color_type), (3, 1, png::ColorType::Rgba));
+        assert_eq!(buffer, vec![0, 0, 0, 255, 128, 128, 128, 255, 255, 255, 255, 255]);
+
+        let grayscale = encode_png_grayscale(3, 1, &[0, 128, 255]).unwrap();
+        assert!(grayscale.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn downscale()
+    {
+        let pixels = [0, 255, 10, 10, 255, 0, 10, 10, 40, 40, 40, 0];
+        let (width, height, scaled) = downscale_grayscale(4, 3, &pixels, 2);
+
+        assert_eq!((width, height), (2, 2));
+        assert_eq!(scaled, vec![128, 10, 40, 20]);
+        assert_eq!(downscale_grayscale(4, 3, &pixels, 8).2, pixels.to_vec());
+    }
+}
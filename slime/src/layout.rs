@@ -0,0 +1,404 @@
+//! Initial simulation state drawn as an image.
+//!
+//! Pixel colours are read as follows:
+//!
+//! - dark pixels (every channel below 64) are obstacles
+//! - blue pixels are agents, with a random heading
+//! - green pixels are food; touching green pixels form one nutrient source whose strength follows the green intensity
+//! - red pixels are repellent, with a strength following the red intensity
+//! - anything else (e.g. white) is open space
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::cell::*;
+use super::config::*;
+use super::environment::*;
+use super::nutrient::*;
+use super::point::*;
+use super::simulation::*;
+use super::trail_map::*;
+
+#[derive(Debug)]
+pub enum LayoutError {
+    Io(io::Error),
+    Png(png::DecodingError),
+    /// The file is not a PNG or binary PGM/PPM, or its contents are malformed
+    Format(String)
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::Io(error) => write!(f, "could not read layout: {}", error),
+            LayoutError::Png(error) => write!(f, "could not decode layout PNG: {}", error),
+            LayoutError::Format(message) => write!(f, "invalid layout image: {}", message)
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// Size of the pixel data of an image, rejecting empty images and sizes that do not fit in memory
+fn pixel_bytes(width: usize, height: usize, channels: usize) -> Result<usize, LayoutError> {
+    if width == 0 || height == 0 {
+        return Err(LayoutError::Format(String::from("image has no pixels")));
+    }
+
+    width.checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .ok_or_else(|| LayoutError::Format(String::from("image too large")))
+}
+
+impl From<io::Error> for LayoutError {
+    fn from(error: io::Error) -> Self {
+        LayoutError::Io(error)
+    }
+}
+
+impl From<png::DecodingError> for LayoutError {
+    fn from(error: png::DecodingError) -> Self {
+        LayoutError::Png(error)
+    }
+}
+
+/// What a single layout pixel stands for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Feature {
+    Empty,
+    Obstacle,
+    Agent,
+    /// Food with a relative strength between 0-1
+    Food(f64),
+    /// Repellent with a relative strength between 0-1
+    Repellent(f64)
+}
+
+impl Feature {
+    pub fn classify(rgb: [u8; 3]) -> Self {
+        let [r, g, b] = rgb;
+
+        if r < 64 && g < 64 && b < 64 {
+            Feature::Obstacle
+        } else if b >= 128 && r < 128 && g < 128 {
+            Feature::Agent
+        } else if g >= 128 && r < 128 && b < 128 {
+            Feature::Food(g as f64 / 255f64)
+        } else if r >= 128 && g < 128 && b < 128 {
+            Feature::Repellent(r as f64 / 255f64)
+        } else {
+            Feature::Empty
+        }
+    }
+}
+
+/// A decoded layout image
+#[derive(Clone, Debug)]
+pub struct Layout {
+    pub width: usize,
+    pub height: usize,
+    /// RGB pixels, row-major
+    pub pixels: Vec<[u8; 3]>
+}
+
+impl Layout {
+    /// Reads a PNG, binary PGM (P5) or binary PPM (P6) file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LayoutError> {
+        let bytes = fs::read(path)?;
+
+        Self::decode(&bytes)
+    }
+
+    /// Decodes PNG, binary PGM (P5) or binary PPM (P6) bytes, based on the file signature
+    pub fn decode(bytes: &[u8]) -> Result<Self, LayoutError> {
+        if bytes.starts_with(b"\x89PNG") {
+            Self::from_png(bytes)
+        } else if bytes.starts_with(b"P5") || bytes.starts_with(b"P6") {
+            Self::from_pnm(bytes)
+        } else {
+            Err(LayoutError::Format(String::from("expected a PNG, PGM (P5) or PPM (P6) image")))
+        }
+    }
+
+    pub fn from_png(bytes: &[u8]) -> Result<Self, LayoutError> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0u8; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+
+        let channels = info.color_type.samples();
+        let (width, height) = (info.width as usize, info.height as usize);
+        let len = pixel_bytes(width, height, channels)?;
+
+        let pixels = buffer.get(..len)
+            .ok_or_else(|| LayoutError::Format(String::from("truncated pixel data")))?
+            .chunks(channels)
+            .map(|pixel| match channels {
+                // grayscale, with or without alpha
+                1 | 2 => [pixel[0]; 3],
+                _ => [pixel[0], pixel[1], pixel[2]]
+            })
+            .collect();
+
+        Ok(Self { width, height, pixels })
+    }
+
+    pub fn from_pnm(bytes: &[u8]) -> Result<Self, LayoutError> {
+        let format_error = |message: &str| LayoutError::Format(String::from(message));
+
+        // header: magic, width, height and maxval separated by whitespace, with optional comments
+        let mut fields = Vec::new();
+        let mut position = 0;
+
+        while fields.len() < 4 {
+            while position < bytes.len() && bytes[position].is_ascii_whitespace() {
+                position += 1;
+            }
+
+            if position < bytes.len() && bytes[position] == b'#' {
+                while position < bytes.len() && bytes[position] != b'\n' {
+                    position += 1;
+                }
+                continue;
+            }
+
+            let start = position;
+
+            while position < bytes.len() && !bytes[position].is_ascii_whitespace() {
+                position += 1;
+            }
+
+            if start == position {
+                return Err(format_error("truncated header"));
+            }
+
+            fields.push(String::from_utf8_lossy(&bytes[start..position]).into_owned());
+        }
+
+        // exactly one whitespace byte separates the header from the pixels
+        position += 1;
+
+        let channels = match fields[0].as_str() {
+            "P5" => 1,
+            "P6" => 3,
+            _ => return Err(format_error("unsupported PNM format"))
+        };
+
+        let parse = |field: &String| field.parse::<usize>().map_err(|_| format_error("invalid header value"));
+        let (width, height, max) = (parse(&fields[1])?, parse(&fields[2])?, parse(&fields[3])?);
+
+        if max == 0 || max > 255 {
+            return Err(format_error("only 8-bit PNM images are supported"));
+        }
+
+        let end = position.checked_add(pixel_bytes(width, height, channels)?).ok_or_else(|| format_error("image too large"))?;
+        let data = bytes.get(position..end).ok_or_else(|| format_error("truncated pixel data"))?;
+        let scale = |value: u8| (value as usize * 255 / max) as u8;
+
+        let pixels = data
+            .chunks(channels)
+            .map(|pixel| match channels {
+                1 => [scale(pixel[0]); 3],
+                _ => [scale(pixel[0]), scale(pixel[1]), scale(pixel[2])]
+            })
+            .collect();
+
+        Ok(Self { width, height, pixels })
+    }
+
+    pub fn feature(&self, x: usize, y: usize) -> Feature {
+        Feature::classify(self.pixels[y * self.width + x])
+    }
+
+    /// Groups touching food pixels into one nutrient source each, with strengths relative to `strength`
+    pub fn nutrient_sources(&self, strength: Concentration) -> Vec<NutrientSource> {
+        let mut visited = vec![false; self.width * self.height];
+        let mut sources = Vec::new();
+
+        for start in 0..self.pixels.len() {
+            if visited[start] || !matches!(Feature::classify(self.pixels[start]), Feature::Food(_)) {
+                continue;
+            }
+
+            // flood fill the blob of food pixels
+            let mut blob = Vec::new();
+            let mut stack = vec![start];
+            visited[start] = true;
+
+            while let Some(index) = stack.pop() {
+                let (x, y) = (index % self.width, index / self.width);
+                blob.push((x, y));
+
+                let neighbours = [
+                    (x.wrapping_sub(1), y), (x + 1, y),
+                    (x, y.wrapping_sub(1)), (x, y + 1)
+                ];
+
+                for (nx, ny) in neighbours.iter().cloned() {
+                    if nx >= self.width || ny >= self.height {
+                        continue;
+                    }
+
+                    let neighbour = ny * self.width + nx;
+
+                    if !visited[neighbour] && matches!(Feature::classify(self.pixels[neighbour]), Feature::Food(_)) {
+                        visited[neighbour] = true;
+                        stack.push(neighbour);
+                    }
+                }
+            }
+
+            let min_x = blob.iter().map(|(x, _)| *x).min().unwrap();
+            let max_x = blob.iter().map(|(x, _)| *x).max().unwrap();
+            let min_y = blob.iter().map(|(_, y)| *y).min().unwrap();
+            let max_y = blob.iter().map(|(_, y)| *y).max().unwrap();
+
+            let (width, height) = (max_x - min_x + 1, max_y - min_y + 1);
+            let mut weights = vec![0 as Concentration; width * height];
+
+            for (x, y) in blob {
+                if let Feature::Food(relative) = self.feature(x, y) {
+                    weights[(y - min_y) * width + (x - min_x)] = relative as Concentration;
+                }
+            }
+
+            let position = Point::new(min_x as f64, min_y as f64);
+            // the weights are sized to the mask, so this skips the check in `NutrientSource::mask`
+            let shape = SourceShape::Mask { width, height, weights };
+            sources.push(NutrientSource { position, shape, strength, reserve: None });
+        }
+
+        sources
+    }
+
+    /// Obstacles and repellents, with repellent strengths relative to `strength`
+    pub fn environment(&self, strength: Concentration) -> Environment {
+        let mut environment = Environment::new(self.width, self.height);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                match self.feature(x, y) {
+                    Feature::Obstacle => environment.set(x, y, Terrain::obstacle()),
+                    Feature::Repellent(relative) => environment.set(x, y, Terrain::repellent(strength * relative as Concentration)),
+                    _ => {}
+                }
+            }
+        }
+
+        environment
+    }
+
+    /// Agents on every agent pixel, with headings drawn from `rng`
+    pub fn cell_map<R: Rng>(&self, sensor_config: SensorConfig, rng: &mut R) -> Result<CellMap, ConfigError> {
+        let mut cell_map = CellMap::new(self.width, self.height, sensor_config)?;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.feature(x, y) == Feature::Agent {
+                    cell_map.add_cell(Point::new(x as f64, y as f64), rng.gen::<f64>() * 360f64);
+                }
+            }
+        }
+
+        Ok(cell_map)
+    }
+
+    /// Builds a simulation from the layout, the environment size is taken from the image and
+    /// food and repellent strengths are relative to `config.deposition`. Fails when the image is too large for an
+    /// environment (see `CellMap::new`)
+    pub fn into_simulation(&self, config: SimulationConfig) -> Result<Simulation, ConfigError> {
+        let config = SimulationConfig { width: self.width, height: self.height, ..config };
+        let mut rng = SimulationRng::seed_from_u64(config.seed);
+
+        let cell_map = self.cell_map(config.sensor_config, &mut rng)?;
+        let trail_map = TrailMap::new(self.width, self.height);
+
+        let nutrients = self.nutrient_sources(config.deposition);
+        let environment = self.environment(config.deposition);
+
+        let mut simulation = Simulation::with_rng(config, cell_map, trail_map, rng);
+        simulation.nutrients = nutrients;
+        simulation.environment = environment;
+
+        Ok(simulation)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    #[test]
+    fn classify()
+    {
+        assert_eq!(Feature::classify([0, 0, 0]), Feature::Obstacle);
+        assert_eq!(Feature::classify([255, 255, 255]), Feature::Empty);
+        assert_eq!(Feature::classify([0, 0, 255]), Feature::Agent);
+        assert_eq!(Feature::classify([0, 255, 0]), Feature::Food(1f64));
+        assert_eq!(Feature::classify([255, 0, 0]), Feature::Repellent(1f64));
+    }
+
+    #[test]
+    fn pnm_layout()
+    {
+        // 3x2 PPM: obstacle, agent, white / food, food, repellent
+        let mut bytes = b"P6\n# arena\n3 2\n255\n".to_vec();
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 255, 255, 255, 255]);
+        bytes.extend_from_slice(&[0, 255, 0, 0, 128, 0, 255, 0, 0]);
+
+        let layout = Layout::decode(&bytes).unwrap();
+        assert_eq!((layout.width, layout.height), (3, 2));
+
+        let simulation = layout.into_simulation(SimulationConfig::default()).unwrap();
+
+        assert_eq!(simulation.config.width, 3);
+        assert_eq!(simulation.cell_map.live_cells(), 1);
+        assert!(simulation.cell_map.get_cell(Point::new(1, 0)).is_some());
+        assert!(simulation.environment.is_obstacle(Point::new(0, 0)));
+        assert_eq!(simulation.environment.data[5].repellent, 255.0);
+
+        assert_eq!(simulation.nutrients.len(), 1);
+        assert_eq!(simulation.nutrients[0].footprint().count(), 2);
+    }
+
+    #[test]
+    fn png_layout()
+    {
+        let mut bytes = Vec::new();
+
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::Eight);
+
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[0, 255]).unwrap();
+        }
+
+        let layout = Layout::decode(&bytes).unwrap();
+
+        assert_eq!(layout.pixels, vec![[0, 0, 0], [255, 255, 255]]);
+        assert_eq!(layout.feature(0, 0), Feature::Obstacle);
+    }
+
+    #[test]
+    fn unknown_format()
+    {
+        assert!(matches!(Layout::decode(b"GIF89a"), Err(LayoutError::Format(_))));
+
+        // empty images, and dimensions whose pixel data would not fit in memory
+        assert!(matches!(Layout::decode(b"P5 0 4 255\n"), Err(LayoutError::Format(_))));
+        assert!(matches!(Layout::decode(b"P6 3 0 255\n"), Err(LayoutError::Format(_))));
+
+        let huge = format!("P6 {} {} 255\n", usize::MAX / 2, 3);
+        assert!(matches!(Layout::decode(huge.as_bytes()), Err(LayoutError::Format(_))));
+    }
+}
@@ -0,0 +1,28 @@
+//! Physarum transport network simulation, independent of any rendering target.
+//!
+//! Enable the `web` feature for `web_sys::ImageData` conversions.
+
+// `Concentration` is converted with `as f64` throughout, which is a no-op when it already is `f64`
+#![cfg_attr(feature = "double-precision", allow(clippy::unnecessary_cast))]
+
+pub mod simulation;
+pub mod plane;
+pub mod cell;
+pub mod config;
+pub mod boundary;
+pub mod point;
+pub mod trail_map;
+pub mod diffusion;
+pub mod nutrient;
+pub mod environment;
+pub mod layout;
+pub mod recorder;
+pub mod snapshot;
+pub mod sweep;
+pub mod analysis;
+pub mod network;
+pub mod proximity;
+pub mod spectrum;
+pub mod pattern;
+pub mod texture;
+pub mod image;
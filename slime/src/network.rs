@@ -0,0 +1,694 @@
+//! Extraction of the transport network as a graph.
+//!
+//! The trail above a threshold is thinned to a one pixel wide skeleton (Zhang-Suen). Skeleton pixels where lines
+//! end become endpoint nodes, where they meet junction nodes, and where they touch a food source food nodes.
+//! Touching node pixels of the same kind are merged into one node. The lines between nodes become edges,
+//! weighted by their length and the mean trail along them.
+//!
+//! Positions are in pixels with the origin at the top-left and `y` pointing down. Edges of a periodic
+//! environment may wrap around, their paths then jump from one edge of the environment to the other.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+use serde_json::json;
+
+use super::analysis::*;
+use super::boundary::*;
+use super::nutrient::*;
+use super::plane::*;
+use super::point::*;
+use super::simulation::*;
+use super::trail_map::*;
+
+/// Offsets of the 8 neighbours, clockwise from north
+const NEIGHBOURS: [(i64, i64); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    /// A line of the network ends here
+    Endpoint,
+    /// Three or more lines meet here
+    Junction,
+    /// The network touches a nutrient source
+    Food,
+    /// A point on a closed loop without any junctions, so the loop can be represented
+    Loop
+}
+
+impl NodeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeKind::Endpoint => "endpoint",
+            NodeKind::Junction => "junction",
+            NodeKind::Food => "food",
+            NodeKind::Loop => "loop"
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    /// Centre of the node's pixels
+    pub position: Point<f64>,
+    pub kind: NodeKind,
+    pub pixels: Vec<Point<usize>>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edge {
+    /// Index of the first node in `Network::nodes`
+    pub source: usize,
+    /// Index of the second node in `Network::nodes`
+    pub target: usize,
+    /// Length of the path in pixels, diagonal steps count as √2
+    pub length: f64,
+    /// Mean trail concentration along the path
+    pub strength: f64,
+    /// Skeleton pixels between the two nodes
+    pub path: Vec<Point<usize>>
+}
+
+/// Settings for `Network::extract`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NetworkConfig {
+    /// Separates the network from the background
+    pub threshold: Threshold,
+    /// Edges shorter than this (in pixels) that lead to a dead end are removed, 0 keeps every spur
+    pub min_spur_length: f64
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            threshold: Threshold::default(),
+            min_spur_length: 3f64
+        }
+    }
+}
+
+struct Grid {
+    width: usize,
+    height: usize,
+    boundary: BoundaryMode
+}
+
+impl Grid {
+    /// Index of the pixel at an offset from `index`, only periodic environments wrap around
+    fn offset(&self, index: usize, (dx, dy): (i64, i64)) -> Option<usize> {
+        let x = (index % self.width) as i64 + dx;
+        let y = (index / self.width) as i64 + dy;
+
+        if x >= 0 && y >= 0 && x < self.width as i64 && y < self.height as i64 {
+            return Some(y as usize * self.width + x as usize);
+        }
+
+        if self.boundary == BoundaryMode::Periodic {
+            self.boundary.resolve(x, y, self.width, self.height).map(|point| point.y * self.width + point.x)
+        } else {
+            None
+        }
+    }
+
+    /// The 8 neighbours of a pixel, clockwise from north, `false` outside the environment
+    fn ring(&self, mask: &[bool], index: usize) -> [bool; 8] {
+        let mut ring = [false; 8];
+
+        for (value, offset) in ring.iter_mut().zip(NEIGHBOURS.iter()) {
+            *value = self.offset(index, *offset).map(|neighbour| mask[neighbour]).unwrap_or(false);
+        }
+
+        ring
+    }
+
+    fn point(&self, index: usize) -> Point<usize> {
+        Point::new(index % self.width, index / self.width)
+    }
+
+    fn adjacent(&self, a: usize, b: usize) -> bool {
+        NEIGHBOURS.iter().any(|offset| self.offset(a, *offset) == Some(b))
+    }
+}
+
+/// Number of set neighbours and number of unset to set transitions going around the ring
+fn ring_counts(ring: &[bool; 8]) -> (usize, usize) {
+    let count = ring.iter().filter(|set| **set).count();
+    let transitions = (0..8).filter(|i| !ring[*i] && ring[(i + 1) % 8]).count();
+
+    (count, transitions)
+}
+
+/// Thins the set pixels of a mask to 8-connected lines one pixel wide, preserving their topology (Zhang-Suen)
+pub fn skeletonize(mask: &[bool], width: usize, height: usize, boundary: BoundaryMode) -> Vec<bool> {
+    assert_eq!(mask.len(), width * height, "mask does not match dimensions");
+
+    let grid = Grid { width, height, boundary };
+    let mut skeleton = mask.to_vec();
+    let mut removed = Vec::new();
+
+    loop {
+        let mut changed = false;
+
+        for pass in 0..2 {
+            removed.clear();
+
+            for index in 0..skeleton.len() {
+                if !skeleton[index] {
+                    continue;
+                }
+
+                let ring = grid.ring(&skeleton, index);
+                let (count, transitions) = ring_counts(&ring);
+                // ring indices: 0 north, 2 east, 4 south, 6 west
+                let (n, e, s, w) = (ring[0], ring[2], ring[4], ring[6]);
+
+                let side = if pass == 0 {
+                    !(e && s && (n || w))
+                } else {
+                    !(n && w && (e || s))
+                };
+
+                if (2..=6).contains(&count) && transitions == 1 && side {
+                    removed.push(index);
+                }
+            }
+
+            for index in removed.iter() {
+                skeleton[*index] = false;
+            }
+
+            changed |= !removed.is_empty();
+        }
+
+        if !changed {
+            return skeleton;
+        }
+    }
+}
+
+/// The transport network as a graph of nodes and weighted edges
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Network {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>
+}
+
+impl Network {
+    /// Extracts the network of a running simulation, with its nutrient sources as food nodes
+    pub fn from_simulation(simulation: &Simulation, config: &NetworkConfig) -> Self {
+        Self::extract(&simulation.trail_map, &simulation.nutrients, simulation.config.boundary, config)
+    }
+
+    pub fn extract(trail_map: &TrailMap, nutrients: &[NutrientSource], boundary: BoundaryMode, config: &NetworkConfig) -> Self {
+        let (width, height) = (trail_map.width(), trail_map.height());
+        let grid = Grid { width, height, boundary };
+
+        let mask = threshold_mask(trail_map, config.threshold);
+        let skeleton = skeletonize(&mask, width, height, boundary);
+
+        // classify the skeleton pixels
+        let mut kinds: Vec<Option<NodeKind>> = vec![None; skeleton.len()];
+
+        for (index, kind) in kinds.iter_mut().enumerate() {
+            if !skeleton[index] {
+                continue;
+            }
+
+            let (count, transitions) = ring_counts(&grid.ring(&skeleton, index));
+
+            if count == 0 || transitions == 1 {
+                *kind = Some(NodeKind::Endpoint);
+            } else if transitions >= 3 {
+                *kind = Some(NodeKind::Junction);
+            }
+        }
+
+        let mut food_without_network = Vec::new();
+
+        for source in nutrients {
+            let mut touches = false;
+
+            for (pixel, _) in source.footprint() {
+                if let Some(point) = boundary.resolve(pixel.x, pixel.y, width, height) {
+                    let index = point.y * width + point.x;
+
+                    if skeleton[index] {
+                        kinds[index] = Some(NodeKind::Food);
+                        touches = true;
+                    }
+                }
+            }
+
+            if !touches {
+                food_without_network.push(source.centre());
+            }
+        }
+
+        let mut extraction = Extraction {
+            grid,
+            trail_map,
+            skeleton,
+            node_at: vec![None; kinds.len()],
+            visited: vec![false; kinds.len()],
+            direct: HashSet::new(),
+            network: Network::default()
+        };
+
+        extraction.merge_nodes(&kinds);
+
+        // follow the lines leaving every node, then anchor the closed loops that are left over
+        for index in 0..kinds.len() {
+            if extraction.node_at[index].is_some() {
+                extraction.trace_from(index);
+            }
+        }
+
+        for index in 0..kinds.len() {
+            if extraction.skeleton[index] && extraction.node_at[index].is_none() && !extraction.visited[index] {
+                let pixel = extraction.grid.point(index);
+                let id = extraction.network.push_node(NodeKind::Loop, vec![pixel]);
+
+                extraction.node_at[index] = Some(id);
+                extraction.trace_from(index);
+            }
+        }
+
+        let mut network = extraction.network;
+
+        for position in food_without_network {
+            network.nodes.push(Node { position, kind: NodeKind::Food, pixels: Vec::new() });
+        }
+
+        network.prune(config.min_spur_length);
+
+        network
+    }
+
+    fn push_node(&mut self, kind: NodeKind, pixels: Vec<Point<usize>>) -> usize {
+        let n = pixels.len().max(1) as f64;
+        let x = pixels.iter().map(|point| point.x as f64).sum::<f64>() / n;
+        let y = pixels.iter().map(|point| point.y as f64).sum::<f64>() / n;
+
+        self.nodes.push(Node { position: Point::new(x, y), kind, pixels });
+        self.nodes.len() - 1
+    }
+
+    /// Number of edge ends at a node, a self loop counts twice
+    pub fn degree(&self, node: usize) -> usize {
+        self.edges.iter().map(|edge| (edge.source == node) as usize + (edge.target == node) as usize).sum()
+    }
+
+    /// Total length of all edges, in pixels
+    pub fn total_length(&self) -> f64 {
+        self.edges.iter().map(|edge| edge.length).sum()
+    }
+
+    /// Removes short edges leading to an endpoint, and endpoints without edges (including isolated specks)
+    fn prune(&mut self, min_length: f64) {
+        if min_length > 0f64 {
+            let degrees: Vec<usize> = (0..self.nodes.len()).map(|node| self.degree(node)).collect();
+            let nodes = &self.nodes;
+
+            let is_spur = |node: usize| nodes[node].kind == NodeKind::Endpoint && degrees[node] == 1;
+
+            self.edges.retain(|edge| {
+                let dead_end = is_spur(edge.source) != is_spur(edge.target);
+
+                !(dead_end && edge.length < min_length)
+            });
+        }
+
+        let degrees: Vec<usize> = (0..self.nodes.len()).map(|node| self.degree(node)).collect();
+        let keep: Vec<bool> = self.nodes.iter().zip(degrees.iter())
+            .map(|(node, degree)| *degree > 0 || node.kind != NodeKind::Endpoint)
+            .collect();
+
+        // renumber the remaining nodes
+        let mut ids = vec![0usize; self.nodes.len()];
+        let mut next = 0;
+
+        for (id, kept) in ids.iter_mut().zip(keep.iter()) {
+            *id = next;
+            next += *kept as usize;
+        }
+
+        let mut index = 0;
+        self.nodes.retain(|_| {
+            index += 1;
+            keep[index - 1]
+        });
+
+        for edge in self.edges.iter_mut() {
+            edge.source = ids[edge.source];
+            edge.target = ids[edge.target];
+        }
+    }
+
+    /// GraphML document, nodes carry `x`, `y` and `kind`, edges `length` and `strength`
+    pub fn to_graphml(&self) -> String {
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"x\" for=\"node\" attr.name=\"x\" attr.type=\"double\"/>\n",
+            "  <key id=\"y\" for=\"node\" attr.name=\"y\" attr.type=\"double\"/>\n",
+            "  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+            "  <key id=\"length\" for=\"edge\" attr.name=\"length\" attr.type=\"double\"/>\n",
+            "  <key id=\"strength\" for=\"edge\" attr.name=\"strength\" attr.type=\"double\"/>\n",
+            "  <graph id=\"network\" edgedefault=\"undirected\">\n"
+        ));
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            let _ = writeln!(
+                xml,
+                "    <node id=\"n{}\"><data key=\"x\">{}</data><data key=\"y\">{}</data><data key=\"kind\">{}</data></node>",
+                id, node.position.x, node.position.y, node.kind.name()
+            );
+        }
+
+        for (id, edge) in self.edges.iter().enumerate() {
+            let _ = writeln!(
+                xml,
+                "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\"><data key=\"length\">{}</data><data key=\"strength\">{}</data></edge>",
+                id, edge.source, edge.target, edge.length, edge.strength
+            );
+        }
+
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    /// GeoJSON feature collection of `Point` nodes and `LineString` edges, in pixel coordinates
+    pub fn to_geojson(&self) -> String {
+        let mut features = Vec::new();
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            features.push(json!({
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": [node.position.x, node.position.y] },
+                "properties": { "id": id, "kind": node.kind.name() }
+            }));
+        }
+
+        for (id, edge) in self.edges.iter().enumerate() {
+            let (source, target) = (self.nodes[edge.source].position, self.nodes[edge.target].position);
+
+            let mut coordinates = vec![[source.x, source.y]];
+            coordinates.extend(edge.path.iter().map(|point| [point.x as f64, point.y as f64]));
+            coordinates.push([target.x, target.y]);
+
+            features.push(json!({
+                "type": "Feature",
+                "geometry": { "type": "LineString", "coordinates": coordinates },
+                "properties": {
+                    "id": id,
+                    "source": edge.source,
+                    "target": edge.target,
+                    "length": edge.length,
+                    "strength": edge.strength
+                }
+            }));
+        }
+
+        serde_json::to_string_pretty(&json!({ "type": "FeatureCollection", "features": features }))
+            .expect("network always serializes to JSON")
+    }
+
+    /// Graphviz DOT document, node positions are pinned so `neato -n` reproduces the layout
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph network {\n");
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            // Graphviz has `y` pointing up
+            let _ = writeln!(dot, "    n{} [kind=\"{}\", pos=\"{},{}!\"];", id, node.kind.name(), node.position.x, -node.position.y);
+        }
+
+        for edge in self.edges.iter() {
+            let _ = writeln!(dot, "    n{} -- n{} [length={}, strength={}];", edge.source, edge.target, edge.length, edge.strength);
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// State shared by the steps of `Network::extract`
+struct Extraction<'a> {
+    grid: Grid,
+    trail_map: &'a TrailMap,
+    skeleton: Vec<bool>,
+    /// Node each pixel belongs to
+    node_at: Vec<Option<usize>>,
+    /// Line pixels that are already part of an edge
+    visited: Vec<bool>,
+    /// Pairs of touching node pixels that already have an edge
+    direct: HashSet<(usize, usize)>,
+    network: Network
+}
+
+impl<'a> Extraction<'a> {
+    /// Merges touching node pixels of the same kind into nodes
+    fn merge_nodes(&mut self, kinds: &[Option<NodeKind>]) {
+        for start in 0..kinds.len() {
+            let kind = match kinds[start] {
+                Some(kind) if self.node_at[start].is_none() => kind,
+                _ => continue
+            };
+
+            let id = self.network.nodes.len();
+            let mut pixels = Vec::new();
+            let mut stack = vec![start];
+            self.node_at[start] = Some(id);
+
+            while let Some(index) = stack.pop() {
+                pixels.push(self.grid.point(index));
+
+                for offset in NEIGHBOURS.iter() {
+                    if let Some(neighbour) = self.grid.offset(index, *offset) {
+                        if kinds[neighbour] == Some(kind) && self.node_at[neighbour].is_none() {
+                            self.node_at[neighbour] = Some(id);
+                            stack.push(neighbour);
+                        }
+                    }
+                }
+            }
+
+            self.network.push_node(kind, pixels);
+        }
+    }
+
+    /// Adds an edge for every line leaving a node pixel that has not been traced yet
+    fn trace_from(&mut self, index: usize) {
+        let node = self.node_at[index].unwrap();
+
+        for offset in NEIGHBOURS.iter() {
+            let next = match self.grid.offset(index, *offset) {
+                Some(next) if self.skeleton[next] && self.node_at[next] != Some(node) => next,
+                _ => continue
+            };
+
+            if let Some(other) = self.node_at[next] {
+                // two nodes touching directly
+                if self.direct.insert((index.min(next), index.max(next))) {
+                    let strength = (self.trail_map.data[index] as f64 + self.trail_map.data[next] as f64) / 2f64;
+
+                    self.network.edges.push(Edge { source: node, target: other, length: step_length(*offset), strength, path: Vec::new() });
+                }
+            } else if !self.visited[next] {
+                self.trace(node, index, next);
+            }
+        }
+    }
+
+    /// Walks along a line from `first`, next to pixel `from` of node `start`, until it reaches a node
+    fn trace(&mut self, start: usize, from: usize, first: usize) {
+        let grid = &self.grid;
+
+        let mut path = vec![first];
+        let mut length = step_between(grid, from, first);
+        let (mut previous, mut current) = (from, first);
+
+        self.visited[first] = true;
+
+        let target = loop {
+            let candidates: Vec<(usize, (i64, i64))> = NEIGHBOURS.iter()
+                .filter_map(|offset| grid.offset(current, *offset).map(|neighbour| (neighbour, *offset)))
+                .filter(|(neighbour, _)| self.skeleton[*neighbour] && *neighbour != previous && *neighbour != current)
+                // pixels next to the node pixel the line leaves from are traced from there
+                .filter(|(neighbour, _)| previous != from || !grid.adjacent(*neighbour, from))
+                .collect();
+
+            // a line reaching any node ends there, a loop back to the start needs a few pixels
+            let node_at = &self.node_at;
+            let reached = candidates.iter().find(|(neighbour, _)| match node_at[*neighbour] {
+                Some(node) => node != start || path.len() >= 3,
+                None => false
+            });
+
+            if let Some((neighbour, offset)) = reached {
+                length += step_length(*offset);
+                break node_at[*neighbour].unwrap();
+            }
+
+            // straight steps first, so diagonal shortcuts are not taken around corners
+            let visited = &self.visited;
+            let next = candidates.iter()
+                .filter(|(neighbour, _)| !visited[*neighbour] && node_at[*neighbour].is_none())
+                .min_by_key(|(_, (dx, dy))| dx.abs() + dy.abs());
+
+            match next {
+                Some((neighbour, offset)) => {
+                    self.visited[*neighbour] = true;
+                    path.push(*neighbour);
+                    length += step_length(*offset);
+                    previous = current;
+                    current = *neighbour;
+                },
+                None => {
+                    // the line stops without an endpoint (e.g. where thinning left a stub), end it at a new node
+                    let end = path.pop().unwrap();
+                    let id = self.network.push_node(NodeKind::Endpoint, vec![grid.point(end)]);
+                    self.node_at[end] = Some(id);
+
+                    break id;
+                }
+            }
+        };
+
+        let strength = if path.is_empty() {
+            self.trail_map.data[from] as f64
+        } else {
+            path.iter().map(|index| self.trail_map.data[*index] as f64).sum::<f64>() / path.len() as f64
+        };
+
+        let path = path.into_iter().map(|index| self.grid.point(index)).collect();
+
+        self.network.edges.push(Edge { source: start, target, length, strength, path });
+    }
+}
+
+fn step_length((dx, dy): (i64, i64)) -> f64 {
+    if dx != 0 && dy != 0 {
+        std::f64::consts::SQRT_2
+    } else {
+        1f64
+    }
+}
+
+fn step_between(grid: &Grid, from: usize, to: usize) -> f64 {
+    NEIGHBOURS.iter()
+        .find(|offset| grid.offset(from, **offset) == Some(to))
+        .map(|offset| step_length(*offset))
+        .unwrap_or(1f64)
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    fn draw(trail_map: &mut TrailMap, pixels: &[(usize, usize)]) {
+        for (x, y) in pixels {
+            let index = trail_map.get_index(*x, *y);
+            trail_map.data[index] = 10.0;
+        }
+    }
+
+    #[test]
+    fn thinning()
+    {
+        // a 3 pixel thick bar thins to a line along its middle
+        let (width, height) = (12, 7);
+        let mut mask = vec![false; width * height];
+
+        for y in 2..5 {
+            for x in 1..11 {
+                mask[y * width + x] = true;
+            }
+        }
+
+        let skeleton = skeletonize(&mask, width, height, BoundaryMode::Absorbing);
+        let rows: Vec<usize> = (0..skeleton.len()).filter(|i| skeleton[*i]).map(|i| i / width).collect();
+
+        assert!(!rows.is_empty());
+        assert!(rows.iter().all(|row| *row == 3));
+    }
+
+    #[test]
+    fn t_junction()
+    {
+        let mut trail_map = TrailMap::new(21, 15);
+
+        let bar: Vec<(usize, usize)> = (2..19).map(|x| (x, 3)).collect();
+        let stem: Vec<(usize, usize)> = (4..13).map(|y| (10, y)).collect();
+        draw(&mut trail_map, &bar);
+        draw(&mut trail_map, &stem);
+
+        let network = Network::extract(&trail_map, &[], BoundaryMode::Absorbing, &NetworkConfig::default());
+
+        let kinds: Vec<NodeKind> = network.nodes.iter().map(|node| node.kind).collect();
+        assert_eq!(kinds.iter().filter(|kind| **kind == NodeKind::Junction).count(), 1);
+        assert_eq!(kinds.iter().filter(|kind| **kind == NodeKind::Endpoint).count(), 3);
+        assert_eq!(network.edges.len(), 3);
+
+        let junction = kinds.iter().position(|kind| *kind == NodeKind::Junction).unwrap();
+        assert_eq!(network.degree(junction), 3);
+        assert_eq!(network.nodes[junction].position, Point::new(10.0, 3.0));
+
+        // 8 + 8 pixels along the bar and 9 down the stem
+        assert!((network.total_length() - 25.0).abs() < 1e-9);
+        assert!(network.edges.iter().all(|edge| edge.strength == 10.0));
+    }
+
+    #[test]
+    fn food_and_loops()
+    {
+        let mut trail_map = TrailMap::new(30, 20);
+
+        // a line between two food sources, and a separate ring
+        draw(&mut trail_map, &(2..14).map(|x| (x, 5)).collect::<Vec<_>>());
+        let ring: Vec<(usize, usize)> = (18..26).flat_map(|i| vec![(i, 10), (i, 17), (18, i - 8), (25, i - 8)]).collect();
+        draw(&mut trail_map, &ring);
+
+        let food = vec![
+            NutrientSource::point(Point::new(2f64, 5f64), 1.0),
+            NutrientSource::point(Point::new(13f64, 5f64), 1.0),
+            NutrientSource::point(Point::new(2f64, 15f64), 1.0)
+        ];
+
+        let network = Network::extract(&trail_map, &food, BoundaryMode::Absorbing, &NetworkConfig::default());
+
+        let food_nodes: Vec<usize> = (0..network.nodes.len()).filter(|i| network.nodes[*i].kind == NodeKind::Food).collect();
+        assert_eq!(food_nodes.len(), 3);
+
+        let line = network.edges.iter().find(|edge| edge.source != edge.target).unwrap();
+        assert!((line.length - 11.0).abs() < 1e-9);
+
+        let ring = network.edges.iter().find(|edge| edge.source == edge.target).unwrap();
+        assert_eq!(network.nodes[ring.source].kind, NodeKind::Loop);
+        assert_eq!(ring.path.len(), 27);
+
+        // the unreached food source is kept as an isolated node
+        assert_eq!(network.degree(food_nodes[2]), 0);
+    }
+
+    #[test]
+    fn exports()
+    {
+        let mut trail_map = TrailMap::new(10, 5);
+        draw(&mut trail_map, &(1..9).map(|x| (x, 2)).collect::<Vec<_>>());
+
+        let network = Network::extract(&trail_map, &[], BoundaryMode::Absorbing, &NetworkConfig::default());
+        assert_eq!((network.nodes.len(), network.edges.len()), (2, 1));
+
+        let graphml = network.to_graphml();
+        assert_eq!(graphml.matches("<node ").count(), 2);
+        assert!(graphml.contains("source=\"n0\" target=\"n1\""));
+
+        let geojson: serde_json::Value = serde_json::from_str(&network.to_geojson()).unwrap();
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 3);
+        assert_eq!(features[2]["geometry"]["coordinates"].as_array().unwrap().len(), 8);
+
+        assert!(network.to_dot().contains("n0 -- n1 [length=7"));
+    }
+}
@@ -0,0 +1,220 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::boundary::*;
+use super::plane::*;
+use super::point::*;
+use super::trail_map::*;
+
+/// Area over which a nutrient source projects chemoattractant
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SourceShape {
+    /// A single pixel at the source position
+    Point,
+    /// All pixels within `radius` of the source position
+    Disc { radius: f64 },
+    /// A `width` x `height` grid of weights (between 0-1) with its top-left corner at the source position
+    Mask { width: usize, height: usize, weights: Vec<Concentration> }
+}
+
+/// Mask weights that do not match the mask's dimensions
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaskSizeError {
+    pub width: usize,
+    pub height: usize,
+    /// Number of weights given
+    pub weights: usize
+}
+
+impl fmt::Display for MaskSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} mask weights do not fit a {}x{} mask", self.weights, self.width, self.height)
+    }
+}
+
+impl std::error::Error for MaskSizeError {}
+
+/// A food source that deposits chemoattractant into the trail map every step
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NutrientSource {
+    /// Pixel position of the source (centre for points and discs, top-left corner for masks)
+    pub position: Point<f64>,
+    pub shape: SourceShape,
+    /// Chemoattractant added to each covered pixel per step (scaled by the mask weight)
+    pub strength: Concentration,
+    /// Total chemoattractant the source has left to give, `None` for an inexhaustible source
+    pub reserve: Option<Concentration>
+}
+
+impl NutrientSource {
+    pub fn point(position: Point<f64>, strength: Concentration) -> Self {
+        Self { position, shape: SourceShape::Point, strength, reserve: None }
+    }
+
+    pub fn disc(position: Point<f64>, radius: f64, strength: Concentration) -> Self {
+        Self { position, shape: SourceShape::Disc { radius }, strength, reserve: None }
+    }
+
+    /// A mask source, fails unless there is one weight per pixel of the `width` x `height` mask
+    pub fn mask(position: Point<f64>, width: usize, height: usize, weights: Vec<Concentration>, strength: Concentration) -> Result<Self, MaskSizeError> {
+        if width.checked_mul(height) != Some(weights.len()) {
+            return Err(MaskSizeError { width, height, weights: weights.len() });
+        }
+
+        Ok(Self { position, shape: SourceShape::Mask { width, height, weights }, strength, reserve: None })
+    }
+
+    /// Limits the total amount of chemoattractant the source can deposit
+    pub fn with_reserve(mut self, reserve: Concentration) -> Self {
+        self.reserve = Some(reserve);
+        self
+    }
+
+    pub fn is_depleted(&self) -> bool {
+        matches!(self.reserve, Some(reserve) if reserve <= 0.0)
+    }
+
+    /// Centre of the source, the weighted centre of the mask for mask sources
+    pub fn centre(&self) -> Point<f64> {
+        match &self.shape {
+            SourceShape::Point | SourceShape::Disc { .. } => self.position,
+            SourceShape::Mask { .. } => {
+                let (mut total, mut x, mut y) = (0f64, 0f64, 0f64);
+
+                for (point, weight) in self.footprint() {
+                    total += weight as f64;
+                    x += point.x as f64 * weight as f64;
+                    y += point.y as f64 * weight as f64;
+                }
+
+                if total <= 0f64 {
+                    return self.position;
+                }
+
+                Point::new(x / total, y / total)
+            }
+        }
+    }
+
+    /// Pixels covered by the source (possibly outside the map) with the weight of each. The shape is scanned in
+    /// place, so this is cheap to call every step
+    pub fn footprint(&self) -> impl Iterator<Item = (Point<i64>, Concentration)> + '_ {
+        let origin: Point<i64> = self.position.into();
+
+        // top-left corner and size of the rectangle the shape lies in
+        let (corner, width, height) = match &self.shape {
+            SourceShape::Point => (origin, 1, 1),
+            SourceShape::Disc { radius } => {
+                let reach = if *radius > 0f64 { radius.ceil() as i64 } else { 0 };
+
+                (Point::new(origin.x - reach, origin.y - reach), 2 * reach + 1, 2 * reach + 1)
+            },
+            SourceShape::Mask { width, height, .. } => (origin, *width as i64, *height as i64)
+        };
+
+        (0..height).flat_map(move |y| (0..width).map(move |x| (x, y))).filter_map(move |(x, y)| {
+            let pixel = Point::new(corner.x + x, corner.y + y);
+
+            let weight = match &self.shape {
+                SourceShape::Point => 1.0,
+                SourceShape::Disc { radius } => {
+                    let (dx, dy) = (pixel.x as f64 - self.position.x, pixel.y as f64 - self.position.y);
+
+                    if dx * dx + dy * dy <= radius * radius { 1.0 } else { 0.0 }
+                },
+                SourceShape::Mask { width, weights, .. } => weights.get(y as usize * width + x as usize).cloned().unwrap_or(0.0)
+            };
+
+            if weight > 0.0 { Some((pixel, weight)) } else { None }
+        })
+    }
+
+    /// Deposits one step worth of chemoattractant into the trail map, drawing it from the reserve
+    pub fn project(&mut self, trail_map: &mut TrailMap, boundary: BoundaryMode) {
+        if self.is_depleted() {
+            return;
+        }
+
+        let (width, height) = (trail_map.width(), trail_map.height());
+        let mut reserve = self.reserve;
+
+        for (pixel, weight) in self.footprint() {
+            let point = match boundary.resolve(pixel.x, pixel.y, width, height) {
+                Some(point) => point,
+                None => continue
+            };
+
+            let mut amount = self.strength * weight;
+
+            if let Some(reserve) = reserve.as_mut() {
+                amount = amount.min(*reserve);
+                *reserve -= amount;
+            }
+
+            let index = trail_map.get_index(point.x, point.y);
+            trail_map.data[index] += amount;
+
+            if matches!(reserve, Some(reserve) if reserve <= 0.0) {
+                break;
+            }
+        }
+
+        self.reserve = reserve;
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    #[test]
+    fn disc_footprint()
+    {
+        let source = NutrientSource::disc(Point::new(5f64, 5f64), 1f64, 1.0);
+
+        let mut pixels: Vec<Point<i64>> = source.footprint().map(|(point, _)| point).collect();
+        pixels.sort_by_key(|point| (point.y, point.x));
+
+        assert_eq!(pixels, vec![
+            Point::new(5, 4),
+            Point::new(4, 5), Point::new(5, 5), Point::new(6, 5),
+            Point::new(5, 6)
+        ]);
+    }
+
+    #[test]
+    fn project_until_depleted()
+    {
+        let mut trail_map = TrailMap::new(4, 4);
+        let mut source = NutrientSource::mask(Point::new(1f64, 1f64), 2, 1, vec![1.0, 0.5], 10.0).unwrap().with_reserve(20.0);
+
+        source.project(&mut trail_map, BoundaryMode::Periodic);
+
+        assert_eq!(trail_map.get_value(1, 1), Some(&10.0));
+        assert_eq!(trail_map.get_value(2, 1), Some(&5.0));
+        assert_eq!(source.reserve, Some(5.0));
+
+        source.project(&mut trail_map, BoundaryMode::Periodic);
+        source.project(&mut trail_map, BoundaryMode::Periodic);
+
+        assert!(source.is_depleted());
+        assert_eq!(trail_map.get_value(1, 1), Some(&15.0));
+        assert_eq!(trail_map.get_value(2, 1), Some(&5.0));
+    }
+
+    #[test]
+    fn mask()
+    {
+        let source = NutrientSource::mask(Point::new(2f64, 3f64), 3, 1, vec![0.0, 1.0, 0.5], 1.0).unwrap();
+
+        assert_eq!(source.footprint().collect::<Vec<_>>(), vec![(Point::new(3, 3), 1.0), (Point::new(4, 3), 0.5)]);
+        assert!((source.centre().x - 10f64 / 3f64).abs() < 1e-9);
+        assert_eq!(
+            NutrientSource::mask(Point::new(0f64, 0f64), 2, 2, vec![1.0], 1.0),
+            Err(MaskSizeError { width: 2, height: 2, weights: 1 })
+        );
+        assert!(NutrientSource::mask(Point::new(0f64, 0f64), usize::MAX, 2, Vec::new(), 1.0).is_err());
+    }
+}
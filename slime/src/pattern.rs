@@ -0,0 +1,295 @@
+//! Classification of the emergent pattern of a run into the regimes described by Jones (2010): reticular
+//! networks, dynamic reticular networks, labyrinthine paths, spots and stable lattices.
+//!
+//! A series of trail maps taken some steps apart is reduced to a handful of features:
+//!
+//! * the radially averaged power spectrum gives the characteristic spacing of the pattern and how regular it is
+//! * the extracted network gives the skeleton length and how often lines branch
+//! * consecutive frames give how fast the pattern still changes
+//!
+//! The features are then matched against thresholds in `ClassifierConfig`. The defaults are set on idealised
+//! drawings of each regime (see the tests), simulated runs may need different values to tell the regimes apart.
+
+use super::analysis::*;
+use super::boundary::*;
+use super::network::*;
+use super::simulation::*;
+use super::spectrum::*;
+use super::trail_map::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Pattern {
+    /// No structure, the trail is (close to) evenly spread or absent
+    Uniform,
+    /// A network of branching lines that has settled down
+    Reticular,
+    /// A branching network whose lines keep moving, merging and splitting
+    DynamicReticular,
+    /// Long winding lines that rarely branch
+    Labyrinthine,
+    /// Isolated blobs of trail
+    Spots,
+    /// A regularly spaced pattern that no longer changes
+    StableLattice
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 6] = [
+        Pattern::Uniform,
+        Pattern::Reticular,
+        Pattern::DynamicReticular,
+        Pattern::Labyrinthine,
+        Pattern::Spots,
+        Pattern::StableLattice
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pattern::Uniform => "uniform",
+            Pattern::Reticular => "reticular",
+            Pattern::DynamicReticular => "dynamic_reticular",
+            Pattern::Labyrinthine => "labyrinthine",
+            Pattern::Spots => "spots",
+            Pattern::StableLattice => "stable_lattice"
+        }
+    }
+}
+
+/// Measurements of a series of trail maps, structural features are taken from the last frame
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PatternFeatures {
+    /// Spacing of the strongest periodic component (in pixels), 0 without any structure
+    pub dominant_wavelength: f64,
+    /// See `RadialSpectrum::peak_prominence`
+    pub spectral_peak: f64,
+    /// Standard deviation of the trail relative to its mean
+    pub variation: f64,
+    /// Fraction of the environment above the threshold
+    pub coverage: f64,
+    /// Connected regions of the trail above the threshold
+    pub components: usize,
+    /// Network length per pixel of the environment
+    pub skeleton_density: f64,
+    /// Fraction of the network's endpoints and junctions that are endpoints, lines that branch into a mesh have
+    /// few dead ends
+    pub dead_ends: f64,
+    /// Network length per connected region (in pixels), close to 0 for blobs
+    pub component_length: f64,
+    /// Mean absolute change between consecutive frames, each scaled to a maximum of 1
+    pub change_rate: f64
+}
+
+impl PatternFeatures {
+    pub fn measure(frames: &[TrailMap], boundary: BoundaryMode, config: &ClassifierConfig) -> Self {
+        let trail_map = match frames.last() {
+            Some(trail_map) => trail_map,
+            None => return Self::default()
+        };
+
+        let spectrum = RadialSpectrum::measure(trail_map);
+        let (mean, variance) = trail_statistics(trail_map);
+
+        let network = Network::extract(trail_map, &[], boundary, &NetworkConfig { threshold: config.threshold, ..NetworkConfig::default() });
+        let length = network.total_length();
+        let count = |kind: NodeKind| network.nodes.iter().filter(|node| node.kind == kind).count();
+        let (endpoints, junctions) = (count(NodeKind::Endpoint), count(NodeKind::Junction));
+        let components = connected_components(trail_map, config.threshold, boundary);
+
+        let changes: Vec<f64> = frames.windows(2).map(|pair| frame_change(&pair[0], &pair[1])).collect();
+
+        Self {
+            dominant_wavelength: spectrum.dominant_wavelength().unwrap_or(0f64),
+            spectral_peak: spectrum.peak_prominence(),
+            variation: if mean > 0f64 { variance.sqrt() / mean } else { 0f64 },
+            coverage: coverage(trail_map, config.threshold),
+            components,
+            skeleton_density: if trail_map.data.is_empty() { 0f64 } else { length / trail_map.data.len() as f64 },
+            dead_ends: if endpoints + junctions > 0 { endpoints as f64 / (endpoints + junctions) as f64 } else { 0f64 },
+            component_length: if components > 0 { length / components as f64 } else { 0f64 },
+            change_rate: if changes.is_empty() { 0f64 } else { changes.iter().sum::<f64>() / changes.len() as f64 }
+        }
+    }
+}
+
+/// Mean absolute difference of two frames after scaling each to a maximum of 1
+fn frame_change(a: &TrailMap, b: &TrailMap) -> f64 {
+    let n = a.data.len().min(b.data.len());
+
+    if n == 0 {
+        return 0f64;
+    }
+
+    let scale = |trail_map: &TrailMap| {
+        let max = trail_map.max_value() as f64;
+        if max > 0f64 { 1f64 / max } else { 0f64 }
+    };
+    let (scale_a, scale_b) = (scale(a), scale(b));
+
+    a.data.iter().zip(b.data.iter())
+        .map(|(a, b)| (*a as f64 * scale_a - *b as f64 * scale_b).abs())
+        .sum::<f64>() / n as f64
+}
+
+/// Thresholds separating the regimes, checked in the order of the fields
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClassifierConfig {
+    /// Separates the pattern from the background
+    pub threshold: Threshold,
+    /// Below this `variation` the trail counts as uniform
+    pub min_variation: f64,
+    /// A `spectral_peak` at least this high with a `change_rate` at most `max_stable_change` is a stable lattice
+    pub min_lattice_peak: f64,
+    /// Highest `change_rate` of a pattern that has settled down, depends on how many steps apart the frames are
+    /// (tuned on frames 25 steps apart)
+    pub max_stable_change: f64,
+    /// Below this `component_length` (in pixels) the regions are spots rather than lines
+    pub max_spot_length: f64,
+    /// Above this fraction of `dead_ends` the lines branch too rarely for a reticular network
+    pub max_reticular_dead_ends: f64
+}
+
+impl Default for ClassifierConfig {
+    fn default() -> Self {
+        Self {
+            threshold: Threshold::default(),
+            min_variation: 0.25f64,
+            min_lattice_peak: 8f64,
+            max_stable_change: 0.05f64,
+            max_spot_length: 6f64,
+            max_reticular_dead_ends: 0.4f64
+        }
+    }
+}
+
+impl ClassifierConfig {
+    pub fn classify(&self, features: &PatternFeatures) -> Pattern {
+        if features.variation < self.min_variation || features.components == 0 {
+            Pattern::Uniform
+        } else if features.spectral_peak >= self.min_lattice_peak && features.change_rate <= self.max_stable_change {
+            Pattern::StableLattice
+        } else if features.component_length < self.max_spot_length {
+            Pattern::Spots
+        } else if features.dead_ends > self.max_reticular_dead_ends {
+            Pattern::Labyrinthine
+        } else if features.change_rate > self.max_stable_change {
+            Pattern::DynamicReticular
+        } else {
+            Pattern::Reticular
+        }
+    }
+}
+
+/// Measures and classifies a series of trail maps
+pub fn classify(frames: &[TrailMap], boundary: BoundaryMode, config: &ClassifierConfig) -> (Pattern, PatternFeatures) {
+    let features = PatternFeatures::measure(frames, boundary, config);
+
+    (config.classify(&features), features)
+}
+
+/// Runs `steps` steps, keeping up to `frames` copies of the trail map `interval` steps apart with the last one
+/// taken after the final step
+pub fn record_frames(simulation: &mut Simulation, steps: usize, frames: usize, interval: usize) -> Vec<TrailMap> {
+    let interval = interval.max(1);
+    let count = frames.max(1).min(steps / interval + 1);
+    let warmup = steps - (count - 1) * interval;
+
+    simulation.step(warmup);
+
+    let mut recorded = vec![simulation.trail_map.clone()];
+
+    while recorded.len() < count {
+        simulation.step(interval);
+        recorded.push(simulation.trail_map.clone());
+    }
+
+    recorded
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::config::*;
+
+    fn draw(width: usize, height: usize, f: impl Fn(usize, usize) -> f64) -> TrailMap {
+        let mut trail_map = TrailMap::new(width, height);
+
+        for (index, value) in trail_map.data.iter_mut().enumerate() {
+            *value = f(index % width, index / width) as Concentration;
+        }
+
+        trail_map
+    }
+
+    fn label(frames: &[TrailMap]) -> Pattern {
+        classify(frames, BoundaryMode::Periodic, &ClassifierConfig::default()).0
+    }
+
+    #[test]
+    fn synthetic_patterns()
+    {
+        // flat and empty maps have no structure
+        assert_eq!(label(&[draw(32, 32, |_, _| 5.0)]), Pattern::Uniform);
+        assert_eq!(label(&[TrailMap::new(32, 32)]), Pattern::Uniform);
+        assert_eq!(label(&[]), Pattern::Uniform);
+
+        // a regular grid of dots
+        let dots = draw(64, 64, |x, y| if x % 8 < 2 && y % 8 < 2 { 10.0 } else { 0.0 });
+        assert_eq!(label(&[dots.clone(), dots.clone()]), Pattern::StableLattice);
+
+        // dots scattered irregularly, and moving between frames
+        let scatter = |shift: usize| draw(64, 64, move |x, y| {
+            let cell = (x / 8) * 31 + (y / 8) * 17 + shift;
+            if (x + cell) % 8 < 2 && (y + cell * 3) % 8 < 2 { 10.0 } else { 0.0 }
+        });
+        assert_eq!(label(&[scatter(0), scatter(5)]), Pattern::Spots);
+
+        // long winding lines that end without branching
+        let waves = |shift: f64| draw(64, 64, move |x, y| {
+            let centre = 16.0 + 6.0 * (2.0 * PI * (y as f64 + shift) / 64.0).sin();
+            if (x as f64 % 32.0 - centre).abs() < 1.0 && (y + x / 32 * 16) % 32 < 24 { 10.0 } else { 0.0 }
+        });
+        assert_eq!(label(&[waves(0.0), waves(16.0)]), Pattern::Labyrinthine);
+
+        // an irregular mesh of lines, settled or still moving
+        let mesh = |shift: usize| draw(64, 64, move |x, y| {
+            let column = [3, 17, 26, 44, 55].contains(&((x + shift) % 64));
+            let row = [6, 21, 35, 50].contains(&y);
+            if column || row { 10.0 } else { 0.0 }
+        });
+        assert_eq!(label(&[mesh(0), mesh(0)]), Pattern::Reticular);
+        assert_eq!(label(&[mesh(0), mesh(7)]), Pattern::DynamicReticular);
+    }
+
+    #[test]
+    fn features()
+    {
+        let frames = [TrailMap::new(8, 8), draw(8, 8, |x, _| x as f64)];
+        let features = PatternFeatures::measure(&frames, BoundaryMode::Absorbing, &ClassifierConfig::default());
+
+        // a blank frame scales to all zeros, so the change is the mean of x / 7
+        assert!((features.change_rate - 0.5).abs() < 1e-6);
+        assert_eq!(frame_change(&frames[1], &frames[1]), 0.0);
+        assert_eq!(features.components, 1);
+    }
+
+    #[test]
+    fn frames()
+    {
+        let config = SimulationConfig { width: 16, height: 16, ..SimulationConfig::default() };
+
+        let mut simulation = Simulation::new_random(config.clone(), 0.1f64).unwrap();
+        let frames = record_frames(&mut simulation, 10, 3, 4);
+        assert_eq!(simulation.step_count, 10);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[2].data, simulation.trail_map.data);
+
+        // fewer steps than the frames span
+        let mut simulation = Simulation::new_random(config, 0.1f64).unwrap();
+        assert_eq!(record_frames(&mut simulation, 5, 3, 4).len(), 2);
+        assert_eq!(simulation.step_count, 5);
+    }
+}
+
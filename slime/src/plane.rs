@@ -0,0 +1,70 @@
+use super::boundary::*;
+use super::config::*;
+use super::point::*;
+
+pub trait Plane<T> {
+
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn data(&self) -> &Vec<T>;
+
+    fn get_coords(&self, index: usize) -> Point<usize> {
+        let y = index / self.width();
+        let x = index % self.width();
+
+        Point { x, y }
+    }
+
+    fn get_index(&self, x: usize, y: usize) -> usize {
+        (y * self.width()) + x
+    }
+    
+    fn get_value(&self, x: usize, y: usize) -> Option<&T> {
+        self.data().get(self.get_index(x, y))
+    }
+
+    fn get_value_point<U: Into<Point<usize>>>(&self, point: U) -> Option<&T> {
+        let discrete: Point<usize> = point.into();
+        self.data().get(self.get_index(discrete.x, discrete.y))
+    }
+
+    /// Looks up a value at a coordinate that may lie outside the plane, resolving it with the given boundary mode
+    fn get_value_bounded<U: Into<Point<i64>>>(&self, point: U, boundary: BoundaryMode) -> Option<&T> {
+        let discrete: Point<i64> = point.into();
+        let resolved = boundary.resolve(discrete.x, discrete.y, self.width(), self.height())?;
+
+        self.get_value(resolved.x, resolved.y)
+    }
+
+    /// Averages `value` over a sensor window of the given width and shape centred on `point`,
+    /// pixels that the boundary mode places outside of the plane count as zero
+    fn sample_with<F: Fn(&T) -> f64>(&self, point: Point<f64>, width: usize, shape: SensorShape, boundary: BoundaryMode, value: F) -> f64 {
+        let centre: Point<i64> = point.into();
+
+        let width = width.max(1) as i64;
+        let start = -((width - 1) / 2);
+        let end = start + width - 1;
+        let radius = (width - 1) as f64 / 2f64;
+        let radius_squared = radius * radius;
+
+        let mut sum = 0f64;
+        let mut n = 0u32;
+
+        for dy in start..=end {
+            for dx in start..=end {
+                if shape == SensorShape::Circle && (dx * dx + dy * dy) as f64 > radius_squared {
+                    continue;
+                }
+
+                n += 1;
+
+                if let Some(pixel) = self.get_value_bounded(Point::new(centre.x + dx, centre.y + dy), boundary) {
+                    sum += value(pixel);
+                }
+            }
+        }
+
+        sum / n as f64
+    }
+
+}
\ No newline at end of file
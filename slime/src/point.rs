@@ -1,15 +1,30 @@
+use std::cmp::Ordering;
 use std::ops::Add;
-use std::{f64::consts::PI, ops::Mul};
-use std::convert::TryFrom;
+use std::ops::Mul;
 
+use serde::{Deserialize, Serialize};
 
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Point<T> {
     pub x: T,
     pub y: T
 }
 
+/// Points order by row (`y`) first, then column (`x`)
+impl<T: Ord> Ord for Point<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.y.cmp(&other.y).then_with(|| self.x.cmp(&other.x))
+    }
+}
+
+impl<T: Ord> PartialOrd for Point<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl<T, U> Mul<U> for Point<T> where U: Into<T>, T: Mul<Output = T> + Copy {
     type Output = Self;
     fn mul(self, rhs: U) -> Self::Output {
@@ -44,13 +59,6 @@ impl Point<f64> {
     }
 }
 
-impl Point<usize> {
-    pub fn get_index(&self, width: usize, height: usize) -> usize {
-        // TODO: something is wrong here, fix it
-        ((width * (self.y)) + self.x) % (width * height)
-    }
-}
-
 impl From<Point<f64>> for Point<usize> {
     fn from(point: Point<f64>) -> Self {
         Self {
@@ -0,0 +1,492 @@
+//! Proximity graphs over food positions, and scores comparing an extracted network against them.
+//!
+//! The graphs nest: minimum spanning tree ⊆ relative neighbourhood graph ⊆ Gabriel graph ⊆ Delaunay
+//! triangulation, and Physarum networks typically fall somewhere in between. Distances are Euclidean and do not
+//! wrap around periodic edges.
+
+use std::collections::HashSet;
+
+use super::network::*;
+use super::point::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProximityKind {
+    MinimumSpanningTree,
+    RelativeNeighbourhood,
+    Gabriel,
+    Delaunay
+}
+
+impl ProximityKind {
+    pub const ALL: [ProximityKind; 4] = [
+        ProximityKind::MinimumSpanningTree,
+        ProximityKind::RelativeNeighbourhood,
+        ProximityKind::Gabriel,
+        ProximityKind::Delaunay
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProximityKind::MinimumSpanningTree => "mst",
+            ProximityKind::RelativeNeighbourhood => "rng",
+            ProximityKind::Gabriel => "gabriel",
+            ProximityKind::Delaunay => "delaunay"
+        }
+    }
+}
+
+fn distance(a: Point<f64>, b: Point<f64>) -> f64 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+/// An undirected graph on a set of points, every edge stored once with the lower index first
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProximityGraph {
+    pub kind: ProximityKind,
+    pub points: Vec<Point<f64>>,
+    pub edges: Vec<(usize, usize)>
+}
+
+impl ProximityGraph {
+    pub fn build(kind: ProximityKind, points: &[Point<f64>]) -> Self {
+        let delaunay = delaunay_edges(points);
+
+        let edges = match kind {
+            ProximityKind::Delaunay => delaunay,
+            ProximityKind::Gabriel => gabriel_edges(points, &delaunay),
+            ProximityKind::RelativeNeighbourhood => relative_neighbourhood_edges(points, &delaunay),
+            ProximityKind::MinimumSpanningTree => minimum_spanning_tree(points, &delaunay)
+        };
+
+        Self { kind, points: points.to_vec(), edges }
+    }
+
+    pub fn total_length(&self) -> f64 {
+        self.edges.iter().map(|(a, b)| distance(self.points[*a], self.points[*b])).sum()
+    }
+
+    /// See `fault_tolerance`, with every point as a terminal
+    pub fn fault_tolerance(&self) -> f64 {
+        let terminals: Vec<usize> = (0..self.points.len()).collect();
+
+        fault_tolerance(self.points.len(), &self.edges, &terminals)
+    }
+}
+
+fn ordered(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// Edges of the Delaunay triangulation (Bowyer-Watson), collinear points are joined in order along their line
+pub fn delaunay_edges(points: &[Point<f64>]) -> Vec<(usize, usize)> {
+    let n = points.len();
+
+    if n < 2 {
+        return Vec::new();
+    }
+
+    let (min_x, max_x) = points.iter().fold((f64::MAX, f64::MIN), |(lo, hi), point| (lo.min(point.x), hi.max(point.x)));
+    let (min_y, max_y) = points.iter().fold((f64::MAX, f64::MIN), |(lo, hi), point| (lo.min(point.y), hi.max(point.y)));
+    let span = (max_x - min_x).max(max_y - min_y).max(1f64);
+    let (mid_x, mid_y) = ((min_x + max_x) / 2f64, (min_y + max_y) / 2f64);
+
+    // a triangle comfortably containing every point, its corners are indices n, n + 1 and n + 2
+    let mut vertices = points.to_vec();
+    vertices.push(Point::new(mid_x - 20f64 * span, mid_y - span));
+    vertices.push(Point::new(mid_x, mid_y + 20f64 * span));
+    vertices.push(Point::new(mid_x + 20f64 * span, mid_y - span));
+
+    let mut triangles: Vec<[usize; 3]> = vec![[n, n + 1, n + 2]];
+
+    for (index, point) in points.iter().enumerate() {
+        let (bad, good): (Vec<[usize; 3]>, Vec<[usize; 3]>) = triangles.into_iter()
+            .partition(|triangle| in_circumcircle(&vertices, triangle, *point));
+
+        // the boundary of the hole left by the removed triangles are the edges only one of them has
+        let mut boundary: Vec<(usize, usize)> = Vec::new();
+
+        for triangle in bad.iter() {
+            for (a, b) in [(triangle[0], triangle[1]), (triangle[1], triangle[2]), (triangle[2], triangle[0])] {
+                let shared = bad.iter().filter(|other| other.contains(&a) && other.contains(&b)).count() > 1;
+
+                if !shared {
+                    boundary.push((a, b));
+                }
+            }
+        }
+
+        triangles = good;
+        triangles.extend(boundary.into_iter().map(|(a, b)| [a, b, index]));
+    }
+
+    let mut edges = HashSet::new();
+
+    let real = |triangle: &&[usize; 3]| triangle.iter().all(|vertex| *vertex < n) && orientation(&vertices, triangle).abs() > 1e-9;
+
+    for triangle in triangles.iter().filter(real) {
+        edges.insert(ordered(triangle[0], triangle[1]));
+        edges.insert(ordered(triangle[1], triangle[2]));
+        edges.insert(ordered(triangle[2], triangle[0]));
+    }
+
+    if edges.is_empty() {
+        // every point is on one line, so there are no triangles
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|a, b| {
+            let (a, b) = (points[*a], points[*b]);
+            a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y))
+        });
+
+        let mut edges: Vec<(usize, usize)> = order.windows(2).map(|pair| ordered(pair[0], pair[1])).collect();
+        edges.sort_unstable();
+
+        return edges;
+    }
+
+    let mut edges: Vec<(usize, usize)> = edges.into_iter().collect();
+    edges.sort_unstable();
+    edges
+}
+
+/// Twice the signed area of a triangle, positive when its corners go counter-clockwise (with `y` up)
+fn orientation(vertices: &[Point<f64>], triangle: &[usize; 3]) -> f64 {
+    let (a, b, c) = (vertices[triangle[0]], vertices[triangle[1]], vertices[triangle[2]]);
+
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+fn in_circumcircle(vertices: &[Point<f64>], triangle: &[usize; 3], point: Point<f64>) -> bool {
+    let (a, b, c) = (vertices[triangle[0]], vertices[triangle[1]], vertices[triangle[2]]);
+
+    let (ax, ay) = (a.x - point.x, a.y - point.y);
+    let (bx, by) = (b.x - point.x, b.y - point.y);
+    let (cx, cy) = (c.x - point.x, c.y - point.y);
+
+    let determinant = (ax * ax + ay * ay) * (bx * cy - cx * by)
+        - (bx * bx + by * by) * (ax * cy - cx * ay)
+        + (cx * cx + cy * cy) * (ax * by - bx * ay);
+
+    // the sign depends on the orientation of the triangle
+    if orientation(vertices, triangle) > 0f64 {
+        determinant > 1e-9
+    } else {
+        determinant < -1e-9
+    }
+}
+
+/// Delaunay edges whose diametral circle holds no other point
+pub fn gabriel_edges(points: &[Point<f64>], delaunay: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    delaunay.iter()
+        .filter(|(a, b)| {
+            let (p, q) = (points[*a], points[*b]);
+            let centre = Point::new((p.x + q.x) / 2f64, (p.y + q.y) / 2f64);
+            let radius = distance(p, q) / 2f64;
+
+            points.iter().enumerate().all(|(i, r)| i == *a || i == *b || distance(centre, *r) >= radius)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Delaunay edges with no other point closer to both of its ends than they are to each other
+pub fn relative_neighbourhood_edges(points: &[Point<f64>], delaunay: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    delaunay.iter()
+        .filter(|(a, b)| {
+            let (p, q) = (points[*a], points[*b]);
+            let length = distance(p, q);
+
+            points.iter().enumerate().all(|(i, r)| i == *a || i == *b || distance(p, *r).max(distance(q, *r)) >= length)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Kruskal's minimum spanning tree, which always lies within the Delaunay triangulation
+pub fn minimum_spanning_tree(points: &[Point<f64>], delaunay: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut candidates = delaunay.to_vec();
+    candidates.sort_by(|(a, b), (c, d)| {
+        distance(points[*a], points[*b]).total_cmp(&distance(points[*c], points[*d]))
+    });
+
+    let mut sets = DisjointSets::new(points.len());
+    let mut tree: Vec<(usize, usize)> = candidates.into_iter().filter(|(a, b)| sets.union(*a, *b)).collect();
+
+    tree.sort_unstable();
+    tree
+}
+
+struct DisjointSets {
+    parents: Vec<usize>
+}
+
+impl DisjointSets {
+    fn new(n: usize) -> Self {
+        Self { parents: (0..n).collect() }
+    }
+
+    fn find(&mut self, mut item: usize) -> usize {
+        while self.parents[item] != item {
+            self.parents[item] = self.parents[self.parents[item]];
+            item = self.parents[item];
+        }
+
+        item
+    }
+
+    /// Joins the sets of `a` and `b`, false if they already were one set
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a] = b;
+
+        a != b
+    }
+}
+
+/// Fraction of edges that can fail on their own without cutting any terminals off from terminals they were
+/// connected to. A tree scores 0, a network with a detour around every edge 1
+pub fn fault_tolerance(node_count: usize, edges: &[(usize, usize)], terminals: &[usize]) -> f64 {
+    if edges.is_empty() {
+        return 0f64;
+    }
+
+    let components = |skip: Option<usize>| {
+        let mut sets = DisjointSets::new(node_count);
+
+        for (i, (a, b)) in edges.iter().enumerate() {
+            if Some(i) != skip {
+                sets.union(*a, *b);
+            }
+        }
+
+        terminals.iter().map(|terminal| sets.find(*terminal)).collect::<Vec<usize>>()
+    };
+
+    let intact = components(None);
+    let connected = |labels: &[usize], i: usize, j: usize| labels[i] == labels[j];
+
+    let tolerated = (0..edges.len())
+        .filter(|skip| {
+            let labels = components(Some(*skip));
+
+            (0..terminals.len()).all(|i| (i + 1..terminals.len()).all(|j| connected(&intact, i, j) == connected(&labels, i, j)))
+        })
+        .count();
+
+    tolerated as f64 / edges.len() as f64
+}
+
+/// The food sources a network connects: two food positions are linked when a path through the network joins
+/// them without passing another food node. Network food nodes are matched to the nearest food position
+pub fn food_graph(network: &Network, food: &[Point<f64>]) -> Vec<(usize, usize)> {
+    let food_of: Vec<Option<usize>> = network.nodes.iter()
+        .map(|node| match node.kind {
+            NodeKind::Food => nearest(food, node.position),
+            _ => None
+        })
+        .collect();
+
+    let mut neighbours = vec![Vec::new(); network.nodes.len()];
+
+    for edge in network.edges.iter() {
+        neighbours[edge.source].push(edge.target);
+        neighbours[edge.target].push(edge.source);
+    }
+
+    let mut links = HashSet::new();
+
+    for (start, source) in food_of.iter().enumerate() {
+        let source = match source {
+            Some(source) => *source,
+            None => continue
+        };
+
+        // flood through the non-food nodes
+        let mut seen = vec![false; network.nodes.len()];
+        let mut stack = vec![start];
+        seen[start] = true;
+
+        while let Some(node) = stack.pop() {
+            for next in neighbours[node].iter() {
+                if seen[*next] {
+                    continue;
+                }
+
+                seen[*next] = true;
+
+                match food_of[*next] {
+                    Some(target) if target != source => {
+                        links.insert(ordered(source, target));
+                    },
+                    Some(_) => {},
+                    None => stack.push(*next)
+                }
+            }
+        }
+    }
+
+    let mut links: Vec<(usize, usize)> = links.into_iter().collect();
+    links.sort_unstable();
+    links
+}
+
+fn nearest(points: &[Point<f64>], position: Point<f64>) -> Option<usize> {
+    (0..points.len()).min_by(|a, b| distance(points[*a], position).total_cmp(&distance(points[*b], position)))
+}
+
+/// How an extracted network measures up against a proximity graph on the same food positions
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    pub kind: ProximityKind,
+    /// Food-to-food links of the network that are also edges of the proximity graph, as a fraction of the links
+    pub precision: f64,
+    /// Edges of the proximity graph the network also links, as a fraction of the edges
+    pub recall: f64,
+    /// Total length of the network divided by the total length of the proximity graph
+    pub length_ratio: f64,
+    pub network_fault_tolerance: f64,
+    pub reference_fault_tolerance: f64
+}
+
+/// Scores a network against a proximity graph, see `Comparison`
+pub fn compare(network: &Network, reference: &ProximityGraph) -> Comparison {
+    let links = food_graph(network, &reference.points);
+
+    let reference_edges: HashSet<(usize, usize)> = reference.edges.iter().cloned().collect();
+    let shared = links.iter().filter(|link| reference_edges.contains(link)).count() as f64;
+
+    let ratio = |part: f64, whole: usize| if whole == 0 { 0f64 } else { part / whole as f64 };
+
+    let terminals: Vec<usize> = (0..network.nodes.len()).filter(|node| network.nodes[*node].kind == NodeKind::Food).collect();
+    let edges: Vec<(usize, usize)> = network.edges.iter().map(|edge| (edge.source, edge.target)).collect();
+
+    let reference_length = reference.total_length();
+
+    Comparison {
+        kind: reference.kind,
+        precision: ratio(shared, links.len()),
+        recall: ratio(shared, reference_edges.len()),
+        length_ratio: if reference_length > 0f64 { network.total_length() / reference_length } else { 0f64 },
+        network_fault_tolerance: fault_tolerance(network.nodes.len(), &edges, &terminals),
+        reference_fault_tolerance: reference.fault_tolerance()
+    }
+}
+
+/// Scores a network against every kind of proximity graph on the given food positions
+pub fn compare_all(network: &Network, food: &[Point<f64>]) -> Vec<Comparison> {
+    ProximityKind::ALL.iter().map(|kind| compare(network, &ProximityGraph::build(*kind, food))).collect()
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    fn square_with_centre() -> Vec<Point<f64>> {
+        vec![
+            Point::new(0f64, 0f64),
+            Point::new(10f64, 0f64),
+            Point::new(10f64, 10f64),
+            Point::new(0f64, 10f64),
+            Point::new(5f64, 5f64)
+        ]
+    }
+
+    #[test]
+    fn graphs_nest()
+    {
+        let points = square_with_centre();
+
+        let delaunay = ProximityGraph::build(ProximityKind::Delaunay, &points);
+        // the four sides and the four spokes to the centre
+        assert_eq!(delaunay.edges.len(), 8);
+        assert!(delaunay.edges.contains(&(0, 4)));
+        assert!(!delaunay.edges.contains(&(0, 2)));
+
+        // the centre lies on the diametral circle of every side, which still counts as outside
+        let gabriel = ProximityGraph::build(ProximityKind::Gabriel, &points);
+        assert_eq!(gabriel.edges.len(), 8);
+
+        // the centre is closer to both ends of every side
+        let rng = ProximityGraph::build(ProximityKind::RelativeNeighbourhood, &points);
+        assert_eq!(rng.edges, vec![(0, 4), (1, 4), (2, 4), (3, 4)]);
+
+        let mst = ProximityGraph::build(ProximityKind::MinimumSpanningTree, &points);
+        assert_eq!(mst.edges.len(), 4);
+        assert!((mst.total_length() - 4.0 * 50f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn random_points_nest()
+    {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_pcg::Pcg32::seed_from_u64(3);
+        let points: Vec<Point<f64>> = (0..40).map(|_| Point::new(rng.gen::<f64>() * 100f64, rng.gen::<f64>() * 100f64)).collect();
+
+        let graphs: Vec<ProximityGraph> = ProximityKind::ALL.iter().map(|kind| ProximityGraph::build(*kind, &points)).collect();
+
+        assert_eq!(graphs[0].edges.len(), points.len() - 1);
+        assert!(graphs[3].edges.len() <= 3 * points.len() - 6);
+
+        for pair in graphs.windows(2) {
+            assert!(pair[0].edges.iter().all(|edge| pair[1].edges.contains(edge)), "{:?} not within {:?}", pair[0].kind, pair[1].kind);
+        }
+    }
+
+    #[test]
+    fn degenerate_points()
+    {
+        let line = vec![Point::new(2f64, 0f64), Point::new(0f64, 0f64), Point::new(1f64, 0f64)];
+
+        assert_eq!(delaunay_edges(&line), vec![(0, 2), (1, 2)]);
+        assert!(delaunay_edges(&line[..1]).is_empty());
+
+        // a position that is not a number sorts last instead of panicking
+        let broken = vec![Point::new(0f64, 0f64), Point::new(1f64, 0f64), Point::new(f64::NAN, 0f64)];
+        assert_eq!(minimum_spanning_tree(&broken, &[(0, 1), (0, 2), (1, 2)])[0], (0, 1));
+        assert_eq!(nearest(&broken, Point::new(0.9f64, 0f64)), Some(1));
+    }
+
+    #[test]
+    fn fault_tolerance_of_cycles()
+    {
+        let triangle = [(0, 1), (1, 2), (0, 2)];
+
+        assert_eq!(fault_tolerance(3, &triangle, &[0, 1, 2]), 1.0);
+        assert_eq!(fault_tolerance(3, &triangle[..2], &[0, 1, 2]), 0.0);
+        // a spur leading away from the terminals can fail safely
+        assert_eq!(fault_tolerance(4, &[(0, 1), (1, 2), (0, 2), (2, 3)], &[0, 1, 2]), 1.0);
+    }
+
+    #[test]
+    fn score_network()
+    {
+        let points = square_with_centre();
+
+        // a network joining every corner to the centre through a junction next to it
+        let mut network = Network::default();
+
+        for point in points.iter() {
+            network.nodes.push(Node { position: *point, kind: NodeKind::Food, pixels: Vec::new() });
+        }
+
+        network.nodes.push(Node { position: Point::new(5f64, 6f64), kind: NodeKind::Junction, pixels: Vec::new() });
+
+        for source in [0, 1, 2, 3, 4] {
+            let length = distance(points[source], Point::new(5f64, 6f64));
+            network.edges.push(Edge { source, target: 5, length, strength: 1.0, path: Vec::new() });
+        }
+
+        // every pair of food sources is joined through the junction
+        let links = food_graph(&network, &points);
+        assert_eq!(links.len(), 10);
+
+        let rng = compare(&network, &ProximityGraph::build(ProximityKind::RelativeNeighbourhood, &points));
+        assert_eq!(rng.recall, 1.0);
+        assert_eq!(rng.precision, 0.4);
+        assert_eq!(rng.network_fault_tolerance, 0.0);
+        assert_eq!(rng.reference_fault_tolerance, 0.0);
+
+        assert_eq!(compare_all(&network, &points).len(), 4);
+    }
+}
@@ -0,0 +1,296 @@
+//! Time-lapse recording of a run as an animated GIF or a numbered PNG sequence.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use super::image::*;
+use super::plane::*;
+use super::simulation::*;
+
+#[derive(Debug)]
+pub enum RecorderError {
+    Io(io::Error),
+    Gif(gif::EncodingError),
+    Png(png::EncodingError),
+    /// GIF frames are limited to 65535 x 65535 pixels
+    TooLarge,
+    /// A simulation of a different size than the GIF was started with, as (width, height)
+    SizeMismatch { expected: (usize, usize), found: (usize, usize) }
+}
+
+impl fmt::Display for RecorderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecorderError::Io(error) => write!(f, "could not write recording: {}", error),
+            RecorderError::Gif(error) => write!(f, "could not encode GIF frame: {}", error),
+            RecorderError::Png(error) => write!(f, "could not encode PNG frame: {}", error),
+            RecorderError::TooLarge => write!(f, "environment is too large for a GIF"),
+            RecorderError::SizeMismatch { expected, found } => write!(
+                f,
+                "frame of {} x {} pixels does not fit a GIF of {} x {} pixels",
+                found.0, found.1, expected.0, expected.1
+            )
+        }
+    }
+}
+
+impl std::error::Error for RecorderError {}
+
+impl From<io::Error> for RecorderError {
+    fn from(error: io::Error) -> Self {
+        RecorderError::Io(error)
+    }
+}
+
+impl From<gif::EncodingError> for RecorderError {
+    fn from(error: gif::EncodingError) -> Self {
+        RecorderError::Gif(error)
+    }
+}
+
+impl From<png::EncodingError> for RecorderError {
+    fn from(error: png::EncodingError) -> Self {
+        RecorderError::Png(error)
+    }
+}
+
+/// Which view of the simulation ends up in the frames
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameSource {
+    /// The trail map in grayscale
+    Trail,
+    /// The trail map with obstacles, food and cells drawn over it
+    Composite
+}
+
+enum Sink {
+    /// Every frame has the size the encoder was created with
+    Gif { encoder: gif::Encoder<Box<dyn Write>>, width: u16, height: u16 },
+    PngSequence { directory: PathBuf, prefix: String, next: usize }
+}
+
+/// Captures a frame every `interval` steps of a simulation
+pub struct Recorder {
+    interval: usize,
+    source: FrameSource,
+    /// Time each GIF frame is shown, in hundredths of a second
+    delay: u16,
+    sink: Sink
+}
+
+impl Recorder {
+    /// Records an animated, looping GIF of `simulation` to `writer`, every frame has the simulation's size
+    pub fn gif<W: Write + 'static>(writer: W, simulation: &Simulation, interval: usize) -> Result<Self, RecorderError> {
+        let (width, height) = (simulation.trail_map.width(), simulation.trail_map.height());
+
+        if width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(RecorderError::TooLarge);
+        }
+
+        let (width, height) = (width as u16, height as u16);
+
+        let writer: Box<dyn Write> = Box::new(writer);
+        let mut encoder = gif::Encoder::new(writer, width, height, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        Ok(Self::new(Sink::Gif { encoder, width, height }, interval))
+    }
+
+    /// Records an animated GIF of `simulation` to a file
+    pub fn gif_file<P: Into<PathBuf>>(path: P, simulation: &Simulation, interval: usize) -> Result<Self, RecorderError> {
+        let file = BufWriter::new(File::create(path.into())?);
+
+        Self::gif(file, simulation, interval)
+    }
+
+    /// Records numbered PNG files (`frame_000000.png`, `frame_000001.png`, ...) into `directory`
+    pub fn png_sequence<P: Into<PathBuf>>(directory: P, interval: usize) -> Result<Self, RecorderError> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+
+        Ok(Self::new(Sink::PngSequence { directory, prefix: String::from("frame"), next: 0 }, interval))
+    }
+
+    fn new(sink: Sink, interval: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            source: FrameSource::Trail,
+            delay: 10,
+            sink
+        }
+    }
+
+    pub fn with_source(mut self, source: FrameSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Sets how long each GIF frame is shown, in hundredths of a second
+    pub fn with_delay(mut self, delay: u16) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Sets the file name prefix of a PNG sequence
+    pub fn with_prefix(mut self, new_prefix: &str) -> Self {
+        if let Sink::PngSequence { prefix, .. } = &mut self.sink {
+            *prefix = String::from(new_prefix);
+        }
+        self
+    }
+
+    /// Writes a frame of the current state, regardless of the interval. A GIF only takes simulations of the size
+    /// it was started with
+    pub fn capture(&mut self, simulation: &Simulation) -> Result<(), RecorderError> {
+        let (width, height) = (simulation.trail_map.width(), simulation.trail_map.height());
+
+        if let Sink::Gif { width: gif_width, height: gif_height, .. } = &self.sink {
+            let expected = (*gif_width as usize, *gif_height as usize);
+
+            if (width, height) != expected {
+                return Err(RecorderError::SizeMismatch { expected, found: (width, height) });
+            }
+        }
+
+        let mut rgba = match self.source {
+            FrameSource::Trail => simulation.trail_map.to_rgba(),
+            FrameSource::Composite => simulation.to_composite_rgba()
+        };
+
+        match &mut self.sink {
+            Sink::Gif { encoder, .. } => {
+                let mut frame = gif::Frame::from_rgba_speed(width as u16, height as u16, &mut rgba, 10);
+                frame.delay = self.delay;
+
+                encoder.write_frame(&frame)?;
+            },
+            Sink::PngSequence { directory, prefix, next } => {
+                let bytes = encode_png_rgba(width, height, &rgba)?;
+
+                fs::write(directory.join(format!("{}_{:06}.png", prefix, next)), bytes)?;
+                *next += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a frame if the simulation is at a multiple of the interval
+    pub fn observe(&mut self, simulation: &Simulation) -> Result<(), RecorderError> {
+        if simulation.step_count.is_multiple_of(self.interval) {
+            self.capture(simulation)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs the simulation for `steps` steps, capturing a frame every interval
+    pub fn record(&mut self, simulation: &mut Simulation, steps: usize) -> Result<(), RecorderError> {
+        for _ in 0..steps {
+            simulation.step(1);
+            self.observe(simulation)?;
+        }
+
+        Ok(())
+    }
+
+    /// Completes the recording, writing the GIF trailer
+    pub fn finish(self) -> Result<(), RecorderError> {
+        if let Sink::Gif { encoder, .. } = self.sink {
+            encoder.into_inner()?.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::config::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn gif_recording()
+    {
+        let config = SimulationConfig { width: 16, height: 12, ..SimulationConfig::default() };
+        let mut simulation = Simulation::new_random(config, 0.1f64).unwrap();
+
+        let buffer = SharedBuffer::default();
+        let mut recorder = Recorder::gif(buffer.clone(), &simulation, 2).unwrap().with_source(FrameSource::Composite);
+
+        recorder.capture(&simulation).unwrap();
+        recorder.record(&mut simulation, 6).unwrap();
+        recorder.finish().unwrap();
+
+        let bytes = buffer.0.borrow().clone();
+        assert!(bytes.starts_with(b"GIF89a"));
+
+        let mut decoder = gif::DecodeOptions::new().read_info(bytes.as_slice()).unwrap();
+        let mut frames = 0;
+
+        while decoder.read_next_frame().unwrap().is_some() {
+            frames += 1;
+        }
+
+        // the initial state plus steps 2, 4 and 6
+        assert_eq!(frames, 4);
+        assert_eq!((decoder.width(), decoder.height()), (16, 12));
+
+        // frames of another size are rejected instead of corrupting the GIF
+        let other = Simulation::new_random(SimulationConfig { width: 8, height: 12, ..SimulationConfig::default() }, 0.1f64).unwrap();
+        let mut recorder = Recorder::gif(SharedBuffer::default(), &simulation, 1).unwrap();
+        assert!(matches!(recorder.capture(&other), Err(RecorderError::SizeMismatch { expected: (16, 12), found: (8, 12) })));
+
+        let wide = Simulation::new_random(SimulationConfig { width: 70_000, height: 1, ..SimulationConfig::default() }, 0f64).unwrap();
+        assert!(matches!(Recorder::gif(SharedBuffer::default(), &wide, 1), Err(RecorderError::TooLarge)));
+    }
+
+    #[test]
+    fn png_sequence()
+    {
+        let directory = std::env::temp_dir().join(format!("slime-png-sequence-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        let config = SimulationConfig { width: 10, height: 6, ..SimulationConfig::default() };
+        let mut simulation = Simulation::new_random(config, 0.1f64).unwrap();
+
+        let mut recorder = Recorder::png_sequence(&directory, 3).unwrap().with_prefix("trail");
+        recorder.capture(&simulation).unwrap();
+        recorder.record(&mut simulation, 6).unwrap();
+        recorder.finish().unwrap();
+
+        let mut files: Vec<String> = fs::read_dir(&directory).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+
+        // the initial state plus steps 3 and 6
+        assert_eq!(files, vec!["trail_000000.png", "trail_000001.png", "trail_000002.png"]);
+
+        let decoder = png::Decoder::new(File::open(directory.join(&files[2])).unwrap());
+        let info = decoder.read_info().unwrap().info().clone();
+        assert_eq!((info.width, info.height), (10, 6));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
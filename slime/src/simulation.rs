@@ -0,0 +1,738 @@
+use rand::Rng;
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use rand_pcg::Pcg32;
+
+use super::cell::*;
+use super::plane::*;
+use super::config::*;
+use super::diffusion::*;
+use super::environment::*;
+use super::nutrient::*;
+use super::point::*;
+use super::trail_map::*;
+
+const CELL_COLOUR: [u8; 4] = [255, 210, 0, 255];
+const FOOD_COLOUR: [u8; 4] = [0, 200, 60, 255];
+const OBSTACLE_COLOUR: [u8; 4] = [60, 30, 90, 255];
+
+/// Number of cells (consecutive in row-major order) the motor stage shuffles together, see `motor`
+const MOTOR_BLOCK: usize = 256;
+
+/// Number of steps between re-sorting the cells row-major for the motor stage. Cells move at most `step_size` a step,
+/// so the order stays close to sorted in between
+const MOTOR_SORT_INTERVAL: usize = 8;
+
+/// Random number generator driving every stochastic decision in a simulation
+pub type SimulationRng = Pcg32;
+
+pub struct Simulation {
+    pub cell_map: CellMap,
+    pub trail_map: TrailMap,
+    /// Food sources projecting chemoattractant into the trail map every step
+    pub nutrients: Vec<NutrientSource>,
+    /// Obstacles and repellents
+    pub environment: Environment,
+    /// Parameters, read every step. Change `diffusion` through `set_diffusion`
+    pub config: SimulationConfig,
+    /// Number of steps run so far
+    pub step_count: usize,
+    pub(crate) rng: SimulationRng,
+    /// Order the motor stage visits the blocks and the cells in, kept to avoid allocating every step
+    blocks: Vec<u32>,
+    order: Vec<u32>,
+    /// Cells that died during the motor stage
+    removed: Vec<usize>,
+    /// Kernel built from `config.diffusion`, see `set_diffusion`
+    diffusion_kernel: Kernel
+}
+
+pub enum Direction {
+    LEFT,
+    RIGHT
+}
+
+impl Simulation {
+
+    /// Creates a simulation with its random number generator seeded from `config.seed`
+    pub fn new(config: SimulationConfig, cell_map: CellMap, trail_map: TrailMap) -> Self {
+        let rng = SimulationRng::seed_from_u64(config.seed);
+
+        Self::with_rng(config, cell_map, trail_map, rng)
+    }
+
+    /// Creates a simulation that draws from the given random number generator
+    pub fn with_rng(config: SimulationConfig, cell_map: CellMap, trail_map: TrailMap, rng: SimulationRng) -> Self {
+
+        let cell_count = cell_map.live_cells();
+        let diffusion_kernel = Kernel::new(&config.diffusion);
+
+        Self {
+            cell_map,
+            trail_map,
+            nutrients: Vec::new(),
+            environment: Environment::new(config.width, config.height),
+            config,
+            step_count: 0,
+            rng,
+            blocks: Vec::new(),
+            order: Vec::with_capacity(cell_count),
+            removed: Vec::new(),
+            diffusion_kernel
+        }
+    }
+
+    /// Creates a simulation with randomly placed cells and a random trail map, all drawn from `config.seed`. Fails
+    /// when the environment is too large (see `CellMap::new`)
+    pub fn new_random(config: SimulationConfig, probability: f64) -> Result<Self, ConfigError> {
+        let mut rng = SimulationRng::seed_from_u64(config.seed);
+
+        let cell_map = CellMap::new_random(config.width, config.height, config.sensor_config, probability, &mut rng)?;
+        let trail_map = TrailMap::new_random(config.width, config.height, &mut rng);
+
+        Ok(Self::with_rng(config, cell_map, trail_map, rng))
+    }
+
+    /// Movement stage. The cells are visited in a random order so no cell is favoured when two compete for a spot.
+    /// Cells compete only with their neighbours, so instead of a full shuffle the cells are sorted row-major (every
+    /// `MOTOR_SORT_INTERVAL` steps), cut into blocks of `MOTOR_BLOCK`, and both the blocks and the cells within each
+    /// block are shuffled. Whichever of two neighbours moves first is still random, while memory is accessed one area
+    /// of the environment at a time
+    pub fn motor(&mut self) {
+        if self.step_count.is_multiple_of(MOTOR_SORT_INTERVAL) {
+            self.cell_map.sort_by_pixel();
+        }
+
+        let count = self.cell_map.live_cells();
+
+        self.blocks.clear();
+        self.blocks.extend(0..count.div_ceil(MOTOR_BLOCK) as u32);
+        self.blocks.shuffle(&mut self.rng);
+
+        self.order.clear();
+
+        for block in self.blocks.iter() {
+            let start = *block as usize * MOTOR_BLOCK;
+            let end = (start + MOTOR_BLOCK).min(count);
+
+            self.order.extend(start as u32..end as u32);
+
+            let len = self.order.len();
+            self.order[len - (end - start)..].shuffle(&mut self.rng);
+        }
+
+        self.removed.clear();
+
+        let (width, height) = (self.config.width, self.config.height);
+        let boundary = self.config.boundary;
+
+        for &index in self.order.iter() {
+            let index = index as usize;
+            let position = self.cell_map.positions()[index];
+            let mut direction = self.cell_map.directions()[index];
+
+            if self.config.cd_prob > 0f64 && self.rng.gen::<f64>() < self.config.cd_prob {
+                // random change in direction
+                direction = self.rng.gen::<f64>() * 360f64;
+            }
+
+            let moved = position + (Point::from_degrees(direction) * self.config.step_size as f64);
+
+            let (new_point, new_direction) = match boundary.constrain(moved, direction, width, height) {
+                Some(constrained) => constrained,
+                None => {
+                    // walked off an absorbing edge, the cell dies once every cell has moved
+                    self.removed.push(index);
+                    continue;
+                }
+            };
+
+            let discrete: Point<i64> = new_point.into();
+            let discrete_point = boundary.resolve(discrete.x, discrete.y, width, height).unwrap();
+
+            if self.cell_map.is_occupied(discrete_point) || self.environment.is_obstacle(discrete_point) {
+                // spot is occupied or blocked, don't move
+                // choose random orientation
+                self.cell_map.directions_mut()[index] = self.rng.gen::<f64>() * 360f64;
+            } else {
+                // move to the new coordinate
+                let new_index = self.trail_map.get_index(discrete_point.x, discrete_point.y);
+
+                // deposit trail on trailmap
+                self.trail_map.data[new_index] = self.config.deposition;
+
+                self.cell_map.move_cell(index, new_point, discrete_point);
+                self.cell_map.directions_mut()[index] = new_direction;
+            }
+        }
+
+        self.cell_map.remove_cells(&mut self.removed);
+    }
+
+    /// Sensory stage, sets proper rotation for cells
+    fn sensor(&mut self) {
+        let (positions, directions) = self.cell_map.headings_mut();
+
+        for (position, cell_direction) in positions.iter().zip(directions.iter_mut()) {
+            let sensor_config = self.config.sensor_config;
+            let offset = sensor_config.offset_distance;    
+
+            let point_fw = *position + (Point::from_degrees(*cell_direction) * offset as f64);
+            
+            let angle_fl = *cell_direction - sensor_config.angle;
+            let angle_fr = *cell_direction + sensor_config.angle;
+
+            let point_fl = *position + (Point::from_degrees(angle_fl) * offset as f64);
+            let point_fr = *position + (Point::from_degrees(angle_fr) * offset as f64);
+
+            let boundary = self.config.boundary;
+
+            let (width, shape) = (sensor_config.width, sensor_config.shape);
+
+            // readings below the sensitivity threshold are ignored, repellents count against the reading
+            let (trail_map, environment, s_min) = (&self.trail_map, &self.environment, self.config.s_min);
+            let sense = |point: Point<f64>| {
+                let value = trail_map.sample(point, width, shape, boundary);
+                let repellent = environment.sample_repellent(point, width, shape, boundary);
+
+                (if value < s_min { 0.0 } else { value }) - repellent
+            };
+
+            let fw = sense(point_fw);
+            let fr = sense(point_fr);
+            let fl = sense(point_fl);
+
+            let mut direction: Option<Direction> = None;
+
+            if fw > fl && fw > fr {
+                //stay here
+                continue;
+            } else if fw < fl && fw < fr {
+                //rotate randomly by RA
+                if self.rng.gen::<f64>() > 0.5f64 {
+                    direction = Some(Direction::LEFT);
+                } else {
+                    direction = Some(Direction::RIGHT);
+                }
+            } else if fl < fr {
+                //rotate right by RA
+                direction = Some(Direction::RIGHT);
+            } else if fr < fl {
+                direction = Some(Direction::LEFT);
+            }
+
+            match direction {
+                Some(Direction::LEFT) => {
+                    *cell_direction = (*cell_direction - self.config.rotation_angle) % 360f64;
+                },
+                Some(Direction::RIGHT) => {
+                    *cell_direction = (*cell_direction + self.config.rotation_angle) % 360f64;
+                },
+                None => {
+                    continue;
+                }
+            }   
+        }
+    }
+
+    /// Changes how the trail diffuses. The kernel is built once here rather than every step, so changes to the
+    /// kernel have to go through this instead of `config.diffusion`
+    pub fn set_diffusion(&mut self, diffusion: DiffusionConfig) {
+        self.diffusion_kernel = Kernel::new(&diffusion);
+        self.config.diffusion = diffusion;
+    }
+
+    /// Diffusion stage, with the kernel and rate from the config
+    fn diffuse(&mut self) {
+        let environment = &self.environment;
+
+        self.trail_map.diffuse(
+            &self.diffusion_kernel,
+            self.config.diffusion.rate as Concentration,
+            self.config.boundary,
+            |index| environment.data[index].obstacle
+        );
+    }
+
+    /// Nutrient stage, food sources deposit chemoattractant
+    fn project_nutrients(&mut self) {
+        for source in self.nutrients.iter_mut() {
+            source.project(&mut self.trail_map, self.config.boundary);
+        }
+    }
+
+    /// Population stage, agents divide into free space when there is enough trail and few neighbours,
+    /// and die when crowded or starved
+    fn population(&mut self) {
+        let rules = self.config.population;
+        let (width, height) = (self.config.width, self.config.height);
+        let boundary = self.config.boundary;
+
+        let mut deaths = Vec::new();
+        let mut births = Vec::new();
+
+        for index in 0..self.cell_map.live_cells() {
+            let point = self.cell_map.pixel(index);
+            let trail = *self.trail_map.get_value(point.x, point.y).unwrap_or(&0.0);
+
+            let crowded = self.cell_map.neighbours(point, rules.death_window, boundary) > rules.death_max_neighbours;
+
+            if crowded || trail < rules.death_min_trail {
+                deaths.push(index);
+                continue;
+            }
+
+            let neighbours = self.cell_map.neighbours(point, rules.birth_window, boundary);
+
+            if trail >= rules.birth_min_trail && neighbours >= rules.birth_min_neighbours && neighbours <= rules.birth_max_neighbours {
+                births.push(self.cell_map.cell(index));
+            }
+        }
+
+        self.cell_map.remove_cells(&mut deaths);
+
+        for parent in births {
+            let discrete: Point<i64> = parent.position.into();
+
+            let free: Vec<Point<usize>> = (-1..=1i64)
+                .flat_map(|dy| (-1..=1i64).map(move |dx| (dx, dy)))
+                .filter_map(|(dx, dy)| boundary.resolve(discrete.x + dx, discrete.y + dy, width, height))
+                .filter(|point| !self.cell_map.is_occupied(*point) && !self.environment.is_obstacle(*point))
+                .collect();
+
+            if free.is_empty() {
+                continue;
+            }
+
+            let point = free[self.rng.gen_range(0, free.len())];
+            let direction = self.rng.gen::<f64>() * 360f64;
+
+            self.cell_map.add_cell(Point::new(point.x as f64, point.y as f64), direction);
+        }
+    }
+
+    /// RGBA image of the whole simulation: the trail map in grayscale, with obstacles, food and cells drawn over it
+    pub fn to_composite_rgba(&self) -> Vec<u8> {
+        let mut data = self.trail_map.to_rgba();
+
+        let (width, height) = (self.config.width, self.config.height);
+
+        let mut paint = |point: Point<usize>, colour: [u8; 4]| {
+            if point.x >= width || point.y >= height {
+                return;
+            }
+
+            let index = self.trail_map.get_index(point.x, point.y) * 4;
+            data[index..index + 4].copy_from_slice(&colour);
+        };
+
+        for (index, terrain) in self.environment.data.iter().enumerate() {
+            if terrain.obstacle {
+                paint(self.environment.get_coords(index), OBSTACLE_COLOUR);
+            }
+        }
+
+        for source in self.nutrients.iter() {
+            for (pixel, _) in source.footprint() {
+                if let Some(point) = self.config.boundary.resolve(pixel.x, pixel.y, width, height) {
+                    paint(point, FOOD_COLOUR);
+                }
+            }
+        }
+
+        for index in 0..self.cell_map.live_cells() {
+            paint(self.cell_map.pixel(index), CELL_COLOUR);
+        }
+
+        data
+    }
+
+    pub fn step(&mut self, n: usize) {
+        for _ in 0..n {
+            self.motor();
+            self.project_nutrients();
+            self.sensor();
+            self.trail_map.decay(self.config.decay);
+            self.diffuse();
+            self.trail_map.decay(self.config.diffusion_decay);
+
+            self.step_count += 1;
+
+            let frequency = self.config.population.frequency;
+
+            if frequency > 0 && self.step_count.is_multiple_of(frequency) {
+                self.population();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+    use crate::boundary::*;
+
+    #[test]
+    pub fn simulation()
+    {
+        let config = SimulationConfig { width: 30, height: 20, ..SimulationConfig::default() };
+        let simulation = Simulation::new_random(config, 0.5f64).unwrap();
+
+        assert_eq!(simulation.step_count, 0);
+        assert_eq!((simulation.trail_map.width(), simulation.trail_map.height()), (30, 20));
+        assert_eq!((simulation.environment.width(), simulation.environment.height()), (30, 20));
+        assert!(simulation.cell_map.live_cells() > 0 && simulation.cell_map.live_cells() < 600);
+    }
+
+    #[test]
+    pub fn motor_step()
+    {
+        let mut cell_map = CellMap::new(20, 20, SensorConfig::default()).unwrap();
+        let trail_map = TrailMap::new(20, 20);
+        let sim_conf = SimulationConfig { width: 20, height: 20, ..SimulationConfig::default() };
+
+        cell_map.add_cell(Point::new(2f64, 4f64), 0f64);
+
+        let mut simulation = Simulation::new(sim_conf, cell_map, trail_map);
+
+        simulation.motor();
+
+        // If we get a cell at this point, it moved correctly
+        let cell = simulation.cell_map.get_cell(Point::new(3, 4));
+        assert!(cell.is_some());
+        
+        // Check that the chemoattractant was deposited at this position
+        let val = simulation.trail_map.get_value(3, 4);
+        let empty_val = simulation.trail_map.get_value(2, 4);
+
+        assert!(val.is_some());
+        assert_eq!(val.unwrap(), &255.0);
+        assert_eq!(empty_val.unwrap(), &0.0);
+    }
+
+    #[test]
+    pub fn sensor_step()
+    {
+        let mut cell_map = CellMap::new(30, 30, SensorConfig::default()).unwrap();
+        let mut trail_map = TrailMap::new(30, 30);
+        let sim_conf = SimulationConfig { width: 30, height: 30, ..SimulationConfig::default() };
+
+        cell_map.add_cell(Point::new(10f64, 10f64), 0f64);
+
+        // trail around the right sensor only, 9 pixels out at 45 degrees
+        for y in 14..19 {
+            for x in 14..19 {
+                let index = trail_map.get_index(x, y);
+                trail_map.data[index] = 255.0;
+            }
+        }
+
+        let mut simulation = Simulation::new(sim_conf, cell_map, trail_map);
+
+        simulation.sensor();
+
+        assert_eq!(simulation.cell_map.directions(), &[45f64]);
+    }
+
+    #[test]
+    pub fn diffuse_step()
+    {
+        let cell_map = CellMap::new(5, 5, SensorConfig::default()).unwrap();
+        let mut trail_map = TrailMap::new(5, 5);
+        let sim_conf = SimulationConfig { width: 5, height: 5, ..SimulationConfig::default() };
+
+        trail_map.data[0] = 255.0;
+
+        let mut simulation = Simulation::new(sim_conf, cell_map, trail_map);
+
+        simulation.diffuse();
+
+        let expected: Vec<Concentration> = vec![
+            63.75, 42.5, 0.0, 0.0, 0.0,
+            42.5, 255.0 / 9.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.0, 0.0,
+        ];
+
+        assert_eq!(simulation.trail_map.data, expected);
+    }
+
+    #[test]
+    fn full_step()
+    {
+        let config = SimulationConfig { width: 500, height: 500, ..SimulationConfig::default() };
+
+        let mut cell_map = CellMap::new(config.width, config.height, config.sensor_config).unwrap();
+        let trail_map = TrailMap::new(config.width, config.height);
+
+        cell_map.add_cell(Point::new(1f64, 1f64), 0f64);
+        cell_map.add_cell(Point::new(1f64, 2f64), 25f64);
+        cell_map.add_cell(Point::new(1f64, 3f64), 90f64);
+
+        cell_map.add_cell(Point::new(10f64, 1f64), 0f64);
+        cell_map.add_cell(Point::new(20f64, 2f64), 25f64);
+        cell_map.add_cell(Point::new(30f64, 3f64), 90f64);
+
+        let mut simulation = Simulation::new(config, cell_map, trail_map);
+
+        simulation.step(1);
+
+        assert_eq!(simulation.cell_map.live_cells(), 6);
+    }
+
+    #[test]
+    fn seeded_runs_are_reproducible()
+    {
+        let run = |seed: u64| {
+            let config = SimulationConfig { width: 50, height: 40, seed, ..SimulationConfig::default() };
+            let mut simulation = Simulation::new_random(config, 0.1f64).unwrap();
+
+            simulation.step(10);
+
+            simulation.trail_map.data
+        };
+
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn occupancy_stays_consistent()
+    {
+        let population = PopulationConfig { frequency: 2, ..PopulationConfig::default() };
+        let config = SimulationConfig { width: 40, height: 30, cd_prob: 0.2, population, ..SimulationConfig::default() };
+
+        let mut simulation = Simulation::new_random(config, 0.5f64).unwrap();
+        simulation.step(15);
+        simulation.motor();
+
+        let cell_map = &simulation.cell_map;
+        assert!(cell_map.live_cells() > 0);
+        assert!((0..cell_map.live_cells()).all(|index| cell_map.index_at(cell_map.pixel(index)) == Some(index)));
+        assert_eq!(cell_map.to_grayscale().iter().filter(|value| **value > 0).count(), cell_map.live_cells());
+
+        // every cell is visited exactly once, a block at a time
+        let mut order = simulation.order.clone();
+        let block = order[0] as usize / MOTOR_BLOCK;
+        let block_len = MOTOR_BLOCK.min(order.len() - block * MOTOR_BLOCK);
+        assert!(order[..block_len].iter().all(|index| *index as usize / MOTOR_BLOCK == block));
+
+        order.sort_unstable();
+        assert!(order.iter().enumerate().all(|(i, index)| *index as usize == i));
+    }
+
+    #[test]
+    fn motor_boundaries()
+    {
+        let run = |boundary: BoundaryMode| {
+            let mut cell_map = CellMap::new(10, 10, SensorConfig::default()).unwrap();
+            let config = SimulationConfig { width: 10, height: 10, boundary, ..SimulationConfig::default() };
+
+            cell_map.add_cell(Point::new(9f64, 4f64), 0f64);
+
+            let mut simulation = Simulation::new(config, cell_map, TrailMap::new(10, 10));
+            simulation.motor();
+
+            let cell = simulation.cell_map.cells().next();
+            cell
+        };
+
+        let wrapped = run(BoundaryMode::Periodic).unwrap();
+        assert_eq!(wrapped.position_discrete(), Point::new(0, 4));
+
+        let reflected = run(BoundaryMode::Reflective).unwrap();
+        assert_eq!(reflected.position_discrete(), Point::new(8, 4));
+        assert_eq!(reflected.direction, 180f64);
+
+        let clamped = run(BoundaryMode::Clamped).unwrap();
+        assert_eq!(clamped.position_discrete(), Point::new(9, 4));
+
+        assert!(run(BoundaryMode::Absorbing).is_none());
+    }
+
+    #[test]
+    fn diffuse_periodic()
+    {
+        let mut trail_map = TrailMap::new(5, 5);
+        let sim_conf = SimulationConfig { width: 5, height: 5, boundary: BoundaryMode::Periodic, ..SimulationConfig::default() };
+
+        trail_map.data[0] = 255.0;
+
+        let mut simulation = Simulation::new(sim_conf, CellMap::new(5, 5, SensorConfig::default()).unwrap(), trail_map);
+
+        simulation.diffuse();
+
+        // the corner spreads into the opposite edges and corner
+        assert_eq!(simulation.trail_map.get_value(4, 4), Some(&(255.0 / 9.0)));
+        assert_eq!(simulation.trail_map.get_value(0, 4), Some(&(255.0 / 9.0)));
+        assert_eq!(simulation.trail_map.get_value(2, 2), Some(&0.0));
+    }
+
+    #[test]
+    fn diffusion_config()
+    {
+        let mut trail_map = TrailMap::new(5, 5);
+        trail_map.data[12] = 90.0;
+
+        let sim_conf = SimulationConfig { width: 5, height: 5, ..SimulationConfig::default() };
+        let mut simulation = Simulation::new(sim_conf, CellMap::new(5, 5, SensorConfig::default()).unwrap(), trail_map);
+
+        simulation.config.diffusion.rate = 0f64;
+        simulation.diffuse();
+        assert_eq!(simulation.trail_map.get_value(2, 2), Some(&90.0));
+
+        simulation.set_diffusion(DiffusionConfig { kernel: KernelShape::Mean, radius: 2, rate: 1f64, ..DiffusionConfig::default() });
+        assert_eq!(simulation.config.diffusion.radius, 2);
+        simulation.diffuse();
+        // the clamped edge crops the 5x5 kernel to 3x3 in the corner
+        assert_eq!(simulation.trail_map.get_value(0, 0), Some(&(90.0 / 9.0)));
+        assert_eq!(simulation.trail_map.get_value(2, 2), Some(&(90.0 / 25.0)));
+    }
+
+    #[test]
+    fn decay_step()
+    {
+        let mut trail_map = TrailMap::new(3, 1);
+        trail_map.data = vec![200.0, 100.0, 1.0];
+
+        trail_map.decay(0.5f64);
+        assert_eq!(trail_map.data, vec![100.0, 50.0, 0.5]);
+
+        trail_map.decay(0f64);
+        assert_eq!(trail_map.data, vec![100.0, 50.0, 0.5]);
+
+        trail_map.decay(1f64);
+        assert_eq!(trail_map.data, vec![0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn sensor_threshold()
+    {
+        let run = |s_min: Concentration| {
+            let mut cell_map = CellMap::new(20, 20, SensorConfig::default()).unwrap();
+            let mut trail_map = TrailMap::new(20, 20);
+            let config = SimulationConfig { width: 20, height: 20, s_min, ..SimulationConfig::default() };
+
+            cell_map.add_cell(Point::new(5f64, 10f64), 0f64);
+
+            // weak trail under the right sensor only
+            let right: Point<usize> = (Point::new(5f64, 10f64) + Point::from_degrees(45f64) * 9f64).into();
+            trail_map.data[right.y * 20 + right.x] = 10.0;
+
+            let mut simulation = Simulation::new(config, cell_map, trail_map);
+            simulation.sensor();
+
+            let direction = simulation.cell_map.cells().next().unwrap().direction;
+            direction
+        };
+
+        assert_eq!(run(5.0), 45f64);
+        assert_eq!(run(50.0), 0f64);
+    }
+
+    #[test]
+    fn random_direction_change()
+    {
+        let mut cell_map = CellMap::new(20, 20, SensorConfig::default()).unwrap();
+        let config = SimulationConfig { width: 20, height: 20, cd_prob: 1f64, ..SimulationConfig::default() };
+
+        cell_map.add_cell(Point::new(10f64, 10f64), 0f64);
+
+        let mut simulation = Simulation::new(config, cell_map, TrailMap::new(20, 20));
+        simulation.motor();
+
+        assert_ne!(simulation.cell_map.cells().next().unwrap().direction, 0f64);
+    }
+
+    #[test]
+    fn population_growth_and_death()
+    {
+        let population = PopulationConfig { frequency: 1, birth_min_trail: 10.0, death_min_trail: 1.0, ..PopulationConfig::default() };
+        let config = SimulationConfig { width: 20, height: 20, population, ..SimulationConfig::default() };
+
+        let mut cell_map = CellMap::new(20, 20, SensorConfig::default()).unwrap();
+        let mut trail_map = TrailMap::new(20, 20);
+
+        // one well fed cell and one starving cell
+        cell_map.add_cell(Point::new(5f64, 5f64), 0f64);
+        cell_map.add_cell(Point::new(15f64, 15f64), 0f64);
+        trail_map.data[5 * 20 + 5] = 100.0;
+
+        let mut simulation = Simulation::new(config, cell_map, trail_map);
+        simulation.population();
+
+        assert_eq!(simulation.cell_map.live_cells(), 2);
+        assert!(simulation.cell_map.get_cell(Point::new(5, 5)).is_some());
+        assert!(simulation.cell_map.get_cell(Point::new(15, 15)).is_none());
+        assert_eq!(simulation.cell_map.neighbours(Point::new(5, 5), 3, BoundaryMode::Periodic), 1);
+    }
+
+    #[test]
+    fn population_crowding()
+    {
+        let population = PopulationConfig { frequency: 1, death_window: 3, death_max_neighbours: 5, birth_max_neighbours: 0, ..PopulationConfig::default() };
+        let config = SimulationConfig { width: 10, height: 10, population, ..SimulationConfig::default() };
+
+        let mut cell_map = CellMap::new(10, 10, SensorConfig::default()).unwrap();
+
+        // a full 3x3 block, only the centre has more than 5 neighbours
+        for y in 4..7 {
+            for x in 4..7 {
+                cell_map.add_cell(Point::new(x as f64, y as f64), 0f64);
+            }
+        }
+
+        let mut simulation = Simulation::new(config, cell_map, TrailMap::new(10, 10));
+        simulation.population();
+
+        assert_eq!(simulation.cell_map.live_cells(), 8);
+        assert!(simulation.cell_map.get_cell(Point::new(5, 5)).is_none());
+    }
+
+    #[test]
+    fn obstacles()
+    {
+        let mut cell_map = CellMap::new(10, 10, SensorConfig::default()).unwrap();
+        let mut trail_map = TrailMap::new(10, 10);
+        let config = SimulationConfig { width: 10, height: 10, boundary: BoundaryMode::Clamped, ..SimulationConfig::default() };
+
+        cell_map.add_cell(Point::new(2f64, 2f64), 0f64);
+        trail_map.data[5] = 90.0;
+
+        let mut simulation = Simulation::new(config, cell_map, trail_map);
+        simulation.environment.set(3, 2, Terrain::obstacle());
+        simulation.environment.fill_rect(4, 0, 1, 10, Terrain::obstacle());
+
+        simulation.motor();
+        assert!(simulation.cell_map.get_cell(Point::new(2, 2)).is_some());
+
+        simulation.diffuse();
+        // the wall at x = 4 keeps the trail from spreading to the left
+        assert_eq!(simulation.trail_map.get_value(4, 0), Some(&0.0));
+        assert_eq!(simulation.trail_map.get_value(3, 0), Some(&0.0));
+        assert_eq!(simulation.trail_map.get_value(5, 0), Some(&(90.0 / 4.0)));
+    }
+
+    #[test]
+    fn repellent()
+    {
+        let mut cell_map = CellMap::new(20, 20, SensorConfig::default()).unwrap();
+        let config = SimulationConfig { width: 20, height: 20, s_min: 0.0, ..SimulationConfig::default() };
+
+        cell_map.add_cell(Point::new(5f64, 10f64), 0f64);
+
+        let mut simulation = Simulation::new(config, cell_map, TrailMap::new(20, 20));
+
+        // repellent under the forward and left sensors, so the agent turns right
+        let forward: Point<usize> = (Point::new(5f64, 10f64) + Point::from_degrees(0f64) * 9f64).into();
+        let left: Point<usize> = (Point::new(5f64, 10f64) + Point::from_degrees(-45f64) * 9f64).into();
+        simulation.environment.set(forward.x, forward.y, Terrain::repellent(10.0));
+        simulation.environment.set(left.x, left.y, Terrain::repellent(10.0));
+
+        simulation.sensor();
+
+        assert_eq!(simulation.cell_map.cells().next().unwrap().direction, 45f64);
+    }
+}
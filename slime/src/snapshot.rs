@@ -0,0 +1,292 @@
+//! Saving and restoring the complete state of a simulation, so a run can be resumed exactly.
+//!
+//! A snapshot file starts with the `SLIMESNP` magic bytes, a little-endian `u32` format version and a byte holding
+//! the size of a trail value (4 for `f32`, 8 for `f64` with the `double-precision` feature), followed by the bincode
+//! encoded state: config, step counter, random number generator, cells, trail, nutrient sources and environment.
+//!
+//! Only snapshots of the current format version and precision can be read.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::cell::*;
+use super::config::*;
+use super::environment::*;
+use super::nutrient::*;
+use super::plane::*;
+use super::simulation::*;
+use super::trail_map::*;
+
+const MAGIC: &[u8; 8] = b"SLIMESNP";
+
+/// Version of the snapshot format written and read by this build
+pub const SNAPSHOT_VERSION: u32 = 4;
+
+/// Size in bytes of a trail value in this build
+const PRECISION: u8 = std::mem::size_of::<Concentration>() as u8;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    Encoding(bincode::Error),
+    /// The file is not a snapshot
+    NotASnapshot,
+    /// The snapshot was written in another version of the format than `SNAPSHOT_VERSION`
+    UnsupportedVersion(u32),
+    /// The snapshot stores trail values of another size (in bytes) than this build, see the `double-precision` feature
+    PrecisionMismatch(u8),
+    /// The snapshot decoded, but its parts do not fit together
+    Inconsistent(String)
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "could not access snapshot: {}", error),
+            SnapshotError::Encoding(error) => write!(f, "could not encode or decode snapshot: {}", error),
+            SnapshotError::NotASnapshot => write!(f, "file is not a simulation snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}, only version {} can be read", version, SNAPSHOT_VERSION)
+            },
+            SnapshotError::PrecisionMismatch(size) => write!(
+                f,
+                "snapshot stores {}-bit trail values, but this build uses {}-bit values (see the double-precision feature)",
+                *size as u32 * 8, PRECISION as u32 * 8
+            ),
+            SnapshotError::Inconsistent(message) => write!(f, "inconsistent snapshot: {}", message)
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+impl From<bincode::Error> for SnapshotError {
+    fn from(error: bincode::Error) -> Self {
+        SnapshotError::Encoding(error)
+    }
+}
+
+/// Borrowed view of the state that is written, field order has to match `Snapshot`
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    config: &'a SimulationConfig,
+    step_count: usize,
+    rng: &'a SimulationRng,
+    cell_map: &'a CellMap,
+    trail_map: &'a TrailMap,
+    nutrients: &'a Vec<NutrientSource>,
+    environment: &'a Environment
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+    config: SimulationConfig,
+    step_count: usize,
+    rng: SimulationRng,
+    cell_map: CellMap,
+    trail_map: TrailMap,
+    nutrients: Vec<NutrientSource>,
+    environment: Environment
+}
+
+impl Snapshot {
+    /// Checks the state fits together and rebuilds what is not stored
+    fn check(&mut self) -> Result<(), SnapshotError> {
+        self.config.validate().map_err(|error| SnapshotError::Inconsistent(error.to_string()))?;
+
+        let (width, height) = (self.config.width, self.config.height);
+
+        let planes = [
+            ("trail map", self.trail_map.width(), self.trail_map.height(), self.trail_map.data.len()),
+            ("environment", self.environment.width(), self.environment.height(), self.environment.data.len()),
+            ("cell map", self.cell_map.width(), self.cell_map.height(), width * height)
+        ];
+
+        for (name, plane_width, plane_height, len) in planes.iter() {
+            if (*plane_width, *plane_height) != (width, height) || *len != width * height {
+                return Err(SnapshotError::Inconsistent(format!(
+                    "{} is {}x{}, but the config is {}x{}", name, plane_width, plane_height, width, height
+                )));
+            }
+        }
+
+        for (index, source) in self.nutrients.iter().enumerate() {
+            if let SourceShape::Mask { width, height, weights } = &source.shape {
+                if width.checked_mul(*height) != Some(weights.len()) {
+                    return Err(SnapshotError::Inconsistent(format!(
+                        "nutrient source {} has {} mask weights for a {}x{} mask", index, weights.len(), width, height
+                    )));
+                }
+            }
+        }
+
+        self.cell_map.rebuild_occupancy().map_err(SnapshotError::Inconsistent)
+    }
+}
+
+impl Simulation {
+    /// Writes the complete state of the simulation in the snapshot format
+    pub fn write_snapshot<W: Write>(&self, mut writer: W) -> Result<(), SnapshotError> {
+        let snapshot = SnapshotRef {
+            config: &self.config,
+            step_count: self.step_count,
+            rng: &self.rng,
+            cell_map: &self.cell_map,
+            trail_map: &self.trail_map,
+            nutrients: &self.nutrients,
+            environment: &self.environment
+        };
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+        writer.write_all(&[PRECISION])?;
+        bincode::serialize_into(&mut writer, &snapshot)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Restores a simulation from a snapshot, stepping it gives the same results as the original would have
+    pub fn read_snapshot<R: Read>(mut reader: R) -> Result<Self, SnapshotError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic).map_err(|_| SnapshotError::NotASnapshot)?;
+
+        if &magic != MAGIC {
+            return Err(SnapshotError::NotASnapshot);
+        }
+
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut version)?;
+
+        let version = u32::from_le_bytes(version);
+
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let mut precision = [0u8; 1];
+        reader.read_exact(&mut precision)?;
+
+        if precision[0] != PRECISION {
+            return Err(SnapshotError::PrecisionMismatch(precision[0]));
+        }
+
+        let mut snapshot: Snapshot = bincode::deserialize_from(reader)?;
+        snapshot.check()?;
+
+        let mut simulation = Simulation::with_rng(snapshot.config, snapshot.cell_map, snapshot.trail_map, snapshot.rng);
+        simulation.step_count = snapshot.step_count;
+        simulation.nutrients = snapshot.nutrients;
+        simulation.environment = snapshot.environment;
+
+        Ok(simulation)
+    }
+
+    /// Writes a snapshot to a file
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+        self.write_snapshot(BufWriter::new(File::create(path)?))
+    }
+
+    /// Restores a simulation from a snapshot file
+    pub fn load_snapshot<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        Self::read_snapshot(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+    use crate::point::*;
+
+    #[test]
+    fn resume_is_exact()
+    {
+        let config = SimulationConfig {
+            width: 30,
+            height: 20,
+            cd_prob: 0.1,
+            decay: 0.05,
+            population: PopulationConfig { frequency: 3, ..PopulationConfig::default() },
+            diffusion: DiffusionConfig { kernel: KernelShape::Anisotropic, sigma: 1.5, angle: 30.0, drift: 1.0, rate: 0.8, ..DiffusionConfig::default() },
+            seed: 11,
+            ..SimulationConfig::default()
+        };
+
+        let mut original = Simulation::new_random(config, 0.2f64).unwrap();
+        original.nutrients.push(NutrientSource::disc(Point::new(10f64, 10f64), 2f64, 20.0).with_reserve(500.0));
+        original.environment.fill_rect(20, 5, 2, 10, Terrain::obstacle());
+        original.step(7);
+
+        let mut bytes = Vec::new();
+        original.write_snapshot(&mut bytes).unwrap();
+
+        let mut resumed = Simulation::read_snapshot(bytes.as_slice()).unwrap();
+
+        original.step(13);
+        resumed.step(13);
+
+        assert_eq!(resumed.step_count, 20);
+        assert_eq!(resumed.trail_map.data, original.trail_map.data);
+        assert_eq!(resumed.cell_map, original.cell_map);
+        assert_eq!(resumed.nutrients, original.nutrients);
+    }
+
+    #[test]
+    fn rejects_bad_input()
+    {
+        let simulation = Simulation::new_random(SimulationConfig { width: 4, height: 4, ..SimulationConfig::default() }, 0.5f64).unwrap();
+
+        let mut bytes = Vec::new();
+        simulation.write_snapshot(&mut bytes).unwrap();
+
+        let mut newer = bytes.clone();
+        newer[8] = 5;
+        let mut older = bytes.clone();
+        older[8] = 3;
+        let mut precision = bytes.clone();
+        precision[12] = 12 - PRECISION;
+
+        assert!(matches!(Simulation::read_snapshot(&b"P5 4 4 255"[..]), Err(SnapshotError::NotASnapshot)));
+        assert!(matches!(Simulation::read_snapshot(newer.as_slice()), Err(SnapshotError::UnsupportedVersion(5))));
+        assert!(matches!(Simulation::read_snapshot(older.as_slice()), Err(SnapshotError::UnsupportedVersion(3))));
+        assert!(matches!(Simulation::read_snapshot(precision.as_slice()), Err(SnapshotError::PrecisionMismatch(size)) if size == 12 - PRECISION));
+        assert!(matches!(Simulation::read_snapshot(&bytes[..bytes.len() - 3]), Err(SnapshotError::Encoding(_))));
+    }
+
+    #[test]
+    fn rejects_inconsistent_state()
+    {
+        let inconsistent = |simulation: &Simulation| {
+            let mut bytes = Vec::new();
+            simulation.write_snapshot(&mut bytes).unwrap();
+
+            matches!(Simulation::read_snapshot(bytes.as_slice()), Err(SnapshotError::Inconsistent(_)))
+        };
+
+        let config = SimulationConfig { width: 4, height: 4, ..SimulationConfig::default() };
+
+        let mut simulation = Simulation::new_random(config.clone(), 0.5f64).unwrap();
+        simulation.config.diffusion.kernel = KernelShape::Custom;
+        assert!(inconsistent(&simulation));
+
+        let mut simulation = Simulation::new_random(config, 0.5f64).unwrap();
+        simulation.nutrients.push(NutrientSource {
+            position: Point::new(1f64, 1f64),
+            shape: SourceShape::Mask { width: 2, height: 2, weights: vec![1.0] },
+            strength: 5.0,
+            reserve: None
+        });
+        assert!(inconsistent(&simulation));
+    }
+}
@@ -0,0 +1,390 @@
+//! Spatial frequency content of the trail map: the 2D power spectrum and its radial average, which shows the
+//! characteristic spacing of a pattern as a peak.
+
+use std::f64::consts::PI;
+
+use super::plane::*;
+use super::trail_map::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(self.re * other.re - self.im * other.im, self.re * other.im + self.im * other.re)
+    }
+
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+
+    fn conj(self) -> Complex {
+        Complex::new(self.re, -self.im)
+    }
+
+    fn scale(self, factor: f64) -> Complex {
+        Complex::new(self.re * factor, self.im * factor)
+    }
+
+    fn norm_squared(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+}
+
+/// In-place radix-2 FFT, the length has to be a power of two
+fn fft(data: &mut [Complex]) {
+    let n = data.len();
+
+    if n <= 1 {
+        return;
+    }
+
+    // bit reversal permutation
+    let bits = n.trailing_zeros();
+
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+
+    let mut length = 2;
+
+    while length <= n {
+        let angle = -2f64 * PI / length as f64;
+        let root = Complex::new(angle.cos(), angle.sin());
+
+        for start in (0..n).step_by(length) {
+            let mut twiddle = Complex::new(1f64, 0f64);
+
+            for i in 0..length / 2 {
+                let even = data[start + i];
+                let odd = data[start + i + length / 2].mul(twiddle);
+
+                data[start + i] = even.add(odd);
+                data[start + i + length / 2] = even.sub(odd);
+                twiddle = twiddle.mul(root);
+            }
+        }
+
+        length *= 2;
+    }
+}
+
+/// Forward discrete Fourier transform of a fixed length, built once and applied to every row or column. Powers of
+/// two go straight through the radix-2 FFT, other lengths through Bluestein's algorithm: the transform is
+/// rewritten as a convolution with a chirp, computed with power-of-two FFTs of at least twice the length. Both
+/// are O(n log n) and give the exact DFT of the original length, unlike zero padding, which would shift the
+/// frequencies
+struct Transform {
+    n: usize,
+    /// `exp(-i pi k^2 / n)` for every `k` below `n`, empty for powers of two
+    chirp: Vec<Complex>,
+    /// FFT of the conjugate chirp, wrapped around to negative indices
+    kernel: Vec<Complex>,
+    scratch: Vec<Complex>
+}
+
+impl Transform {
+    fn new(n: usize) -> Self {
+        if n <= 1 || n.is_power_of_two() {
+            return Self { n, chirp: Vec::new(), kernel: Vec::new(), scratch: Vec::new() };
+        }
+
+        // k^2 modulo 2n keeps the angles small, so they stay exact for long rows
+        let chirp: Vec<Complex> = (0..n).map(|k| {
+            let angle = -PI * ((k * k) % (2 * n)) as f64 / n as f64;
+            Complex::new(angle.cos(), angle.sin())
+        }).collect();
+
+        let m = (2 * n - 1).next_power_of_two();
+        let mut kernel = vec![Complex::default(); m];
+
+        for (k, value) in chirp.iter().enumerate() {
+            kernel[k] = value.conj();
+
+            if k > 0 {
+                kernel[m - k] = value.conj();
+            }
+        }
+
+        fft(&mut kernel);
+
+        Self { n, chirp, kernel, scratch: vec![Complex::default(); m] }
+    }
+
+    fn apply(&mut self, data: &mut [Complex]) {
+        assert_eq!(data.len(), self.n, "transform length does not match data");
+
+        if self.chirp.is_empty() {
+            fft(data);
+            return;
+        }
+
+        let m = self.scratch.len();
+
+        for (k, value) in self.scratch.iter_mut().enumerate() {
+            *value = if k < self.n { data[k].mul(self.chirp[k]) } else { Complex::default() };
+        }
+
+        fft(&mut self.scratch);
+
+        // inverse FFT of the product through conjugation: ifft(x) = conj(fft(conj(x))) / m
+        for (value, kernel) in self.scratch.iter_mut().zip(self.kernel.iter()) {
+            *value = value.mul(*kernel).conj();
+        }
+
+        fft(&mut self.scratch);
+
+        for (k, value) in data.iter_mut().enumerate() {
+            *value = self.scratch[k].conj().scale(1f64 / m as f64).mul(self.chirp[k]);
+        }
+    }
+}
+
+/// Squared magnitude of the 2D Fourier transform of the trail with its mean removed, row-major with the zero
+/// frequency at index 0
+pub fn power_spectrum(trail_map: &TrailMap) -> Vec<f64> {
+    let values: Vec<f64> = trail_map.data.iter().map(|value| *value as f64).collect();
+
+    power_spectrum_of(&values, trail_map.width(), trail_map.height())
+}
+
+/// `power_spectrum` of any row-major grid of values
+pub fn power_spectrum_of(values: &[f64], width: usize, height: usize) -> Vec<f64> {
+    assert_eq!(values.len(), width * height, "values do not match dimensions");
+
+    let n = values.len();
+
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let mean = values.iter().sum::<f64>() / n as f64;
+    let mut data: Vec<Complex> = values.iter().map(|value| Complex::new(value - mean, 0f64)).collect();
+
+    let mut rows = Transform::new(width);
+
+    for row in data.chunks_mut(width) {
+        rows.apply(row);
+    }
+
+    let mut columns = Transform::new(height);
+    let mut column = vec![Complex::default(); height];
+
+    for x in 0..width {
+        for y in 0..height {
+            column[y] = data[y * width + x];
+        }
+
+        columns.apply(&mut column);
+
+        for y in 0..height {
+            data[y * width + x] = column[y];
+        }
+    }
+
+    data.into_iter().map(|value| value.norm_squared() / n as f64).collect()
+}
+
+/// Power averaged over rings of equal spatial frequency
+#[derive(Clone, Debug, PartialEq)]
+pub struct RadialSpectrum {
+    /// Side of the square the frequencies are measured against, the shorter side of the map
+    pub size: usize,
+    /// Mean power per ring, ring `k` holds the patterns repeating every `size / k` pixels
+    pub power: Vec<f64>
+}
+
+impl RadialSpectrum {
+    pub fn measure(trail_map: &TrailMap) -> Self {
+        Self::from_power(&power_spectrum(trail_map), trail_map.width(), trail_map.height())
+    }
+
+    /// Radial spectrum of a thresholded mask, set pixels count as 1
+    pub fn of_mask(mask: &[bool], width: usize, height: usize) -> Self {
+        let values: Vec<f64> = mask.iter().map(|set| if *set { 1f64 } else { 0f64 }).collect();
+
+        Self::from_power(&power_spectrum_of(&values, width, height), width, height)
+    }
+
+    /// Averages a 2D power spectrum laid out like `power_spectrum`'s
+    pub fn from_power(spectrum: &[f64], width: usize, height: usize) -> Self {
+        let size = width.min(height);
+
+        let bins = size / 2 + 1;
+        let mut sums = vec![0f64; bins];
+        let mut counts = vec![0usize; bins];
+
+        // frequencies above the Nyquist limit alias to negative ones
+        let signed = |k: usize, n: usize| if k <= n / 2 { k as f64 / n as f64 } else { k as f64 / n as f64 - 1f64 };
+
+        for (index, power) in spectrum.iter().enumerate() {
+            let (fx, fy) = (signed(index % width, width), signed(index / width, height));
+            let bin = ((fx * fx + fy * fy).sqrt() * size as f64).round() as usize;
+
+            if bin < bins {
+                sums[bin] += power;
+                counts[bin] += 1;
+            }
+        }
+
+        let power = sums.iter().zip(counts.iter()).map(|(sum, count)| if *count > 0 { sum / *count as f64 } else { 0f64 }).collect();
+
+        Self { size, power }
+    }
+
+    /// Spacing in pixels of the patterns in ring `bin`
+    pub fn wavelength(&self, bin: usize) -> f64 {
+        if bin == 0 {
+            f64::INFINITY
+        } else {
+            self.size as f64 / bin as f64
+        }
+    }
+
+    /// The ring with the most power, leaving out the zero frequency and rings without a finite power
+    pub fn peak(&self) -> Option<usize> {
+        (1..self.power.len())
+            .filter(|bin| self.power[*bin].is_finite() && self.power[*bin] > 0f64)
+            .max_by(|a, b| self.power[*a].total_cmp(&self.power[*b]))
+    }
+
+    /// Spacing in pixels of the strongest periodic component
+    pub fn dominant_wavelength(&self) -> Option<f64> {
+        self.peak().map(|bin| self.wavelength(bin))
+    }
+
+    /// Exponent of the power law the spectrum falls off with, fitted over every ring with power. Natural
+    /// textures tend to fall off with an exponent around -2
+    pub fn slope(&self) -> Option<f64> {
+        let points: Vec<(f64, f64)> = self.power.iter().enumerate().skip(1).map(|(bin, power)| (bin as f64, *power)).collect();
+
+        log_log_slope(&points)
+    }
+
+    /// Peak power relative to the mean power of the frequencies up to twice the peak's, high for regular patterns
+    /// and close to 1 when the power simply falls off with frequency
+    pub fn peak_prominence(&self) -> f64 {
+        let peak = match self.peak() {
+            Some(peak) => peak,
+            None => return 0f64
+        };
+
+        let band = &self.power[1..=(2 * peak).min(self.power.len() - 1)];
+        let mean = band.iter().sum::<f64>() / band.len() as f64;
+
+        if mean > 0f64 { self.power[peak] / mean } else { 0f64 }
+    }
+}
+
+/// Least squares slope of `ln y` against `ln x`, ignoring points that are not positive in both. `None` with fewer
+/// than two distinct usable `x` values
+pub fn log_log_slope(points: &[(f64, f64)]) -> Option<f64> {
+    let logs: Vec<(f64, f64)> = points.iter()
+        .filter(|(x, y)| *x > 0f64 && *y > 0f64)
+        .map(|(x, y)| (x.ln(), y.ln()))
+        .collect();
+
+    let n = logs.len() as f64;
+    let mean_x = logs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = logs.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let covariance = logs.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum::<f64>();
+    let spread = logs.iter().map(|(x, _)| (x - mean_x).powi(2)).sum::<f64>();
+
+    if logs.len() < 2 || spread <= 0f64 {
+        None
+    } else {
+        Some(covariance / spread)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    fn stripes(width: usize, height: usize, period: f64) -> TrailMap {
+        let mut trail_map = TrailMap::new(width, height);
+
+        for (index, value) in trail_map.data.iter_mut().enumerate() {
+            let x = (index % width) as f64;
+            *value = (10f64 + 5f64 * (2f64 * PI * x / period).sin()) as Concentration;
+        }
+
+        trail_map
+    }
+
+    #[test]
+    fn fft_matches_dft()
+    {
+        // powers of two through the radix-2 FFT, other lengths through Bluestein's algorithm
+        for n in [1, 2, 7, 8, 12, 60, 64] {
+            let input: Vec<Complex> = (0..n).map(|i| Complex::new((i * i % 5) as f64, (i % 3) as f64)).collect();
+
+            let mut fast = input.clone();
+            Transform::new(n).apply(&mut fast);
+
+            let dft: Vec<Complex> = (0..n).map(|k| {
+                input.iter().enumerate().fold(Complex::default(), |sum, (t, value)| {
+                    let angle = -2f64 * PI * (k * t) as f64 / n as f64;
+                    sum.add(value.mul(Complex::new(angle.cos(), angle.sin())))
+                })
+            }).collect();
+
+            for (a, b) in fast.iter().zip(dft.iter()) {
+                assert!((a.re - b.re).abs() < 1e-9 && (a.im - b.im).abs() < 1e-9, "length {}", n);
+            }
+        }
+    }
+
+    #[test]
+    fn stripe_wavelength()
+    {
+        let spectrum = RadialSpectrum::measure(&stripes(64, 64, 8f64));
+        assert_eq!(spectrum.peak(), Some(8));
+        assert_eq!(spectrum.dominant_wavelength(), Some(8.0));
+        assert!(spectrum.peak_prominence() > 10.0);
+
+        // sizes that are not a power of two go through Bluestein's algorithm
+        let spectrum = RadialSpectrum::measure(&stripes(60, 40, 10f64));
+        assert_eq!(spectrum.size, 40);
+        assert_eq!(spectrum.dominant_wavelength(), Some(10.0));
+
+        assert_eq!(RadialSpectrum::measure(&TrailMap::new(16, 16)).peak(), None);
+        assert_eq!(RadialSpectrum::measure(&TrailMap::new(16, 16)).slope(), None);
+
+        // a trail holding NaN has no peak rather than a panic
+        let mut broken = stripes(16, 16, 4f64);
+        broken.data[3] = Concentration::NAN;
+        assert_eq!(RadialSpectrum::measure(&broken).peak(), None);
+
+        // a mask of the same stripes peaks at the same spacing
+        let mask: Vec<bool> = stripes(64, 64, 8f64).data.iter().map(|value| *value > 10.0).collect();
+        assert_eq!(RadialSpectrum::of_mask(&mask, 64, 64).dominant_wavelength(), Some(8.0));
+    }
+
+    #[test]
+    fn slope()
+    {
+        let points: Vec<(f64, f64)> = (1..6).map(|x| (x as f64, 3.0 * (x as f64).powf(-1.5))).collect();
+        assert!((log_log_slope(&points).unwrap() + 1.5).abs() < 1e-9);
+
+        assert_eq!(log_log_slope(&[(1.0, 2.0), (1.0, 3.0), (0.0, 1.0)]), None);
+        assert_eq!(log_log_slope(&[]), None);
+    }
+}
@@ -0,0 +1,555 @@
+//! Parameter sweeps: runs a simulation for every combination of a set of parameter values and
+//! summarises each run, to map out which parameters give which patterns.
+//!
+//! Every run starts from the same seed, so differences between runs come from the parameters alone.
+//! Runs are spread over all cores natively and run one after the other on wasm.
+//!
+//! Each run is labelled with the pattern it formed (see `pattern`), `phase_diagram` lays the labels out over two
+//! of the swept parameters.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+
+use super::analysis::*;
+use super::config::*;
+use super::image::*;
+use super::pattern::*;
+use super::plane::*;
+use super::simulation::*;
+use super::trail_map::*;
+
+/// Most values `parse_values` accepts for a single parameter
+pub const MAX_SWEEP_VALUES: usize = 10_000;
+
+/// Most combinations a sweep runs, see `Sweep::combinations`
+pub const MAX_SWEEP_RUNS: usize = 100_000;
+
+/// Parses a list of values, either comma separated (`22.5,45,90`) or an inclusive range with a step (`0:90:22.5`).
+/// Values have to be finite numbers, and at most `MAX_SWEEP_VALUES` of them
+pub fn parse_values(text: &str) -> Result<Vec<f64>, String> {
+    let parse = |value: &str| match value.trim().parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(number),
+        _ => Err(format!("invalid number: {}", value))
+    };
+
+    let parts: Vec<&str> = text.split(':').collect();
+
+    let values = match parts.as_slice() {
+        [start, end, step] => {
+            let (start, end, step) = (parse(start)?, parse(end)?, parse(step)?);
+
+            if step <= 0f64 || end < start {
+                return Err(format!("invalid range: {}", text));
+            }
+
+            // count the values up front to avoid accumulating rounding errors
+            let n = ((end - start) / step + 1e-9).floor() + 1f64;
+
+            if !n.is_finite() || n > MAX_SWEEP_VALUES as f64 {
+                return Err(format!("range {} has more than {} values", text, MAX_SWEEP_VALUES));
+            }
+
+            (0..n as usize).map(|i| start + i as f64 * step).collect()
+        },
+        [_] => text.split(',').map(parse).collect::<Result<Vec<f64>, String>>()?,
+        _ => return Err(format!("expected a comma separated list or start:end:step, got {}", text))
+    };
+
+    if values.len() > MAX_SWEEP_VALUES {
+        return Err(format!("more than {} values: {}", MAX_SWEEP_VALUES, text));
+    }
+
+    Ok(values)
+}
+
+/// The swept parameters of a single run
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepParameters {
+    pub sensor_angle: f64,
+    pub rotation_angle: f64,
+    pub sensor_offset: usize,
+    pub step_size: usize,
+    pub deposition: Concentration,
+    pub decay: f64
+}
+
+/// A swept parameter, an axis of the phase diagram
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SweepAxis {
+    SensorAngle,
+    RotationAngle,
+    SensorOffset,
+    StepSize,
+    Deposition,
+    Decay
+}
+
+impl SweepAxis {
+    pub const ALL: [SweepAxis; 6] = [
+        SweepAxis::SensorAngle,
+        SweepAxis::RotationAngle,
+        SweepAxis::SensorOffset,
+        SweepAxis::StepSize,
+        SweepAxis::Deposition,
+        SweepAxis::Decay
+    ];
+
+    /// Name of the parameter's CSV column
+    pub fn name(&self) -> &'static str {
+        match self {
+            SweepAxis::SensorAngle => "sensor_angle",
+            SweepAxis::RotationAngle => "rotation_angle",
+            SweepAxis::SensorOffset => "sensor_offset",
+            SweepAxis::StepSize => "step_size",
+            SweepAxis::Deposition => "deposition",
+            SweepAxis::Decay => "decay"
+        }
+    }
+
+    /// Looks an axis up by `name`, dashes may stand in for underscores
+    pub fn from_name(name: &str) -> Option<SweepAxis> {
+        let name = name.replace('-', "_");
+
+        SweepAxis::ALL.iter().cloned().find(|axis| axis.name() == name)
+    }
+
+    pub fn value(&self, parameters: &SweepParameters) -> f64 {
+        match self {
+            SweepAxis::SensorAngle => parameters.sensor_angle,
+            SweepAxis::RotationAngle => parameters.rotation_angle,
+            SweepAxis::SensorOffset => parameters.sensor_offset as f64,
+            SweepAxis::StepSize => parameters.step_size as f64,
+            SweepAxis::Deposition => parameters.deposition as f64,
+            SweepAxis::Decay => parameters.decay
+        }
+    }
+}
+
+impl SweepParameters {
+    /// The base config with these parameters applied
+    pub fn apply(&self, base: &SimulationConfig) -> SimulationConfig {
+        let mut config = base.clone();
+
+        config.sensor_config.angle = self.sensor_angle;
+        config.rotation_angle = self.rotation_angle;
+        config.sensor_config.offset_distance = self.sensor_offset;
+        config.step_size = self.step_size;
+        config.deposition = self.deposition;
+        config.decay = self.decay;
+
+        config
+    }
+}
+
+/// Summary of a finished run
+#[derive(Clone, Debug)]
+pub struct SweepResult {
+    /// Position of the run in `Sweep::combinations`
+    pub index: usize,
+    pub parameters: SweepParameters,
+    pub live_cells: usize,
+    pub mean_trail: f64,
+    pub trail_variance: f64,
+    pub max_trail: f64,
+    /// Fraction of the environment with more than a tenth of the highest trail value
+    pub coverage: f64,
+    /// Connected regions of the trail above a tenth of the highest value
+    pub components: usize,
+    pub heading_alignment: f64,
+    /// The regime the run ended up in
+    pub pattern: Pattern,
+    /// What `pattern` was decided on
+    pub features: PatternFeatures,
+    pub thumbnail_width: usize,
+    pub thumbnail_height: usize,
+    /// Grayscale image of the final trail map, scaled down to the sweep's thumbnail size
+    pub thumbnail: Vec<u8>
+}
+
+impl SweepResult {
+    pub fn thumbnail_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        encode_png_grayscale(self.thumbnail_width, self.thumbnail_height, &self.thumbnail)
+    }
+}
+
+/// Column names of `write_csv`
+pub const CSV_HEADER: &str = "run,sensor_angle,rotation_angle,sensor_offset,step_size,deposition,decay,live_cells,mean_trail,trail_variance,max_trail,coverage,components,heading_alignment,pattern,dominant_wavelength,spectral_peak,dead_ends,change_rate";
+
+/// Writes one row per result, in the order given
+pub fn write_csv<W: Write>(results: &[SweepResult], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "{}", CSV_HEADER)?;
+
+    for result in results {
+        write_csv_row(result, &mut writer)?;
+    }
+
+    writer.flush()
+}
+
+/// Writes the row of a single result, see `CSV_HEADER` for the columns
+pub fn write_csv_row<W: Write>(result: &SweepResult, mut writer: W) -> io::Result<()> {
+    let parameters = &result.parameters;
+
+    writeln!(
+        writer,
+        "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+        result.index,
+        parameters.sensor_angle,
+        parameters.rotation_angle,
+        parameters.sensor_offset,
+        parameters.step_size,
+        parameters.deposition,
+        parameters.decay,
+        result.live_cells,
+        result.mean_trail,
+        result.trail_variance,
+        result.max_trail,
+        result.coverage,
+        result.components,
+        result.heading_alignment,
+        result.pattern.name(),
+        result.features.dominant_wavelength,
+        result.features.spectral_peak,
+        result.features.dead_ends,
+        result.features.change_rate
+    )
+}
+
+/// Lays the patterns out over two swept parameters as a CSV table, `rows` values down the first column and
+/// `columns` values along the first row. Runs that differ only in other parameters share a cell, which holds
+/// their most common pattern (empty when no run has that pair of values)
+pub fn phase_diagram(results: &[SweepResult], rows: SweepAxis, columns: SweepAxis) -> String {
+    let distinct = |axis: SweepAxis| {
+        let mut values: Vec<f64> = results.iter().map(|result| axis.value(&result.parameters)).collect();
+        values.sort_by(|a, b| a.total_cmp(b));
+        values.dedup();
+        values
+    };
+    let (row_values, column_values) = (distinct(rows), distinct(columns));
+
+    let mut table = format!("{}\\{}", rows.name(), columns.name());
+
+    for value in column_values.iter() {
+        let _ = write!(table, ",{}", value);
+    }
+
+    table.push('\n');
+
+    for row in row_values.iter() {
+        let _ = write!(table, "{}", row);
+
+        for column in column_values.iter() {
+            let mut counts = [0usize; Pattern::ALL.len()];
+
+            for result in results {
+                if rows.value(&result.parameters) == *row && columns.value(&result.parameters) == *column {
+                    counts[Pattern::ALL.iter().position(|pattern| *pattern == result.pattern).unwrap()] += 1;
+                }
+            }
+
+            // ties go to the pattern listed first
+            let most = counts.iter().cloned().max().unwrap_or(0);
+            let label = if most == 0 { "" } else { Pattern::ALL[counts.iter().position(|count| *count == most).unwrap()].name() };
+
+            table.push(',');
+            table.push_str(label);
+        }
+
+        table.push('\n');
+    }
+
+    table
+}
+
+/// The values to try for each parameter, every parameter starts out with only its value in the base config
+pub struct Sweep {
+    pub base: SimulationConfig,
+    pub sensor_angles: Vec<f64>,
+    pub rotation_angles: Vec<f64>,
+    pub sensor_offsets: Vec<usize>,
+    pub step_sizes: Vec<usize>,
+    pub depositions: Vec<Concentration>,
+    pub decays: Vec<f64>,
+    /// Steps run per combination
+    pub steps: usize,
+    /// Probability of a pixel starting with a cell
+    pub density: f64,
+    /// Longest side of the thumbnails (in pixels)
+    pub thumbnail_size: usize,
+    /// Trail maps the pattern is classified on, taken at the end of a run
+    pub pattern_frames: usize,
+    /// Steps between those trail maps
+    pub frame_interval: usize,
+    pub classifier: ClassifierConfig
+}
+
+impl Sweep {
+    pub fn new(base: SimulationConfig) -> Self {
+        Self {
+            sensor_angles: vec![base.sensor_config.angle],
+            rotation_angles: vec![base.rotation_angle],
+            sensor_offsets: vec![base.sensor_config.offset_distance],
+            step_sizes: vec![base.step_size],
+            depositions: vec![base.deposition],
+            decays: vec![base.decay],
+            base,
+            steps: 1000,
+            density: 0.1f64,
+            thumbnail_size: 64,
+            pattern_frames: 5,
+            frame_interval: 25,
+            classifier: ClassifierConfig::default()
+        }
+    }
+
+    /// The parameters that take more than one value, in the order of `SweepAxis::ALL`
+    pub fn varied_axes(&self) -> Vec<SweepAxis> {
+        let counts = [
+            self.sensor_angles.len(),
+            self.rotation_angles.len(),
+            self.sensor_offsets.len(),
+            self.step_sizes.len(),
+            self.depositions.len(),
+            self.decays.len()
+        ];
+
+        SweepAxis::ALL.iter().zip(counts.iter()).filter(|(_, count)| **count > 1).map(|(axis, _)| *axis).collect()
+    }
+
+    /// Number of combinations of the parameter values, `None` when it does not fit in a `usize`
+    pub fn run_count(&self) -> Option<usize> {
+        [
+            self.sensor_angles.len(),
+            self.rotation_angles.len(),
+            self.sensor_offsets.len(),
+            self.step_sizes.len(),
+            self.depositions.len(),
+            self.decays.len()
+        ].iter().try_fold(1usize, |count, values| count.checked_mul(*values))
+    }
+
+    /// Every combination of the parameter values, the last parameter (decay) varying fastest. Fails when there are
+    /// more than `MAX_SWEEP_RUNS`
+    pub fn combinations(&self) -> Result<Vec<SweepParameters>, ConfigError> {
+        match self.run_count() {
+            Some(count) if count <= MAX_SWEEP_RUNS => {},
+            _ => return Err(ConfigError::Invalid(format!("a sweep must not have more than {} combinations", MAX_SWEEP_RUNS)))
+        }
+
+        let mut combinations = Vec::new();
+
+        for &sensor_angle in self.sensor_angles.iter() {
+            for &rotation_angle in self.rotation_angles.iter() {
+                for &sensor_offset in self.sensor_offsets.iter() {
+                    for &step_size in self.step_sizes.iter() {
+                        for &deposition in self.depositions.iter() {
+                            for &decay in self.decays.iter() {
+                                combinations.push(SweepParameters {
+                                    sensor_angle,
+                                    rotation_angle,
+                                    sensor_offset,
+                                    step_size,
+                                    deposition,
+                                    decay
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(combinations)
+    }
+
+    /// Runs a single combination to completion
+    pub fn run_one(&self, index: usize, parameters: SweepParameters) -> Result<SweepResult, ConfigError> {
+        let config = parameters.apply(&self.base);
+        config.validate()?;
+
+        let mut simulation = Simulation::new_random(config, self.density)?;
+        let frames = record_frames(&mut simulation, self.steps, self.pattern_frames, self.frame_interval);
+        let (pattern, features) = classify(&frames, simulation.config.boundary, &self.classifier);
+
+        let metrics = Metrics::measure(&simulation, &AnalysisConfig::default());
+        let trail_map = &simulation.trail_map;
+
+        let (thumbnail_width, thumbnail_height, thumbnail) =
+            downscale_grayscale(trail_map.width(), trail_map.height(), &trail_map.to_grayscale(), self.thumbnail_size);
+
+        Ok(SweepResult {
+            index,
+            parameters,
+            live_cells: metrics.live_cells,
+            mean_trail: metrics.mean_trail,
+            trail_variance: metrics.trail_variance,
+            max_trail: trail_map.max_value() as f64,
+            coverage: metrics.coverage,
+            components: metrics.components,
+            heading_alignment: metrics.heading_alignment,
+            pattern,
+            features,
+            thumbnail_width,
+            thumbnail_height,
+            thumbnail
+        })
+    }
+
+    /// Runs every combination, results are in the order of `combinations`
+    pub fn run(&self) -> Result<Vec<SweepResult>, ConfigError> {
+        let mut results = Vec::new();
+
+        self.run_each(|result| {
+            results.push(result);
+            Ok::<(), ConfigError>(())
+        })?;
+
+        Ok(results)
+    }
+
+    /// Runs every combination and hands each result to `on_result` as soon as it and every run before it have
+    /// finished, so the results arrive in the order of `combinations` without being kept until the end. Stops at
+    /// the first error, from a run or from `on_result`
+    pub fn run_each<F, E>(&self, mut on_result: F) -> Result<(), E>
+    where
+        F: FnMut(SweepResult) -> Result<(), E>,
+        E: From<ConfigError>
+    {
+        let combinations: Vec<(usize, SweepParameters)> = self.combinations()?.into_iter().enumerate().collect();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let (sender, receiver) = mpsc::channel();
+
+            thread::scope(|scope| {
+                scope.spawn(move || {
+                    // a failed send means the receiver stopped early, which stops the remaining runs
+                    let _ = combinations.into_par_iter().try_for_each_with(sender, |sender, (index, parameters)| {
+                        sender.send((index, self.run_one(index, parameters))).map_err(|_| ())
+                    });
+                });
+
+                // runs finish out of order, the ones that are early wait here for the runs before them
+                let mut pending = BTreeMap::new();
+                let mut next = 0;
+
+                // the receiver is dropped when this returns, so an early return also stops the sender
+                for (index, result) in receiver {
+                    pending.insert(index, result);
+
+                    while let Some(result) = pending.remove(&next) {
+                        on_result(result?)?;
+                        next += 1;
+                    }
+                }
+
+                Ok(())
+            })
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            for (index, parameters) in combinations {
+                on_result(self.run_one(index, parameters)?)?;
+            }
+
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    #[test]
+    fn values()
+    {
+        assert_eq!(parse_values("22.5,45,90").unwrap(), vec![22.5, 45.0, 90.0]);
+        assert_eq!(parse_values("0:90:22.5").unwrap(), vec![0.0, 22.5, 45.0, 67.5, 90.0]);
+        assert_eq!(parse_values("0.1:0.3:0.1").unwrap().len(), 3);
+        assert!(parse_values("10:0:1").is_err());
+        assert!(parse_values("1,x").is_err());
+
+        // unbounded and oversized ranges
+        assert!(parse_values("0:inf:1").is_err());
+        assert!(parse_values("0:1e300:1").is_err());
+        assert!(parse_values("0:1:1e-9").is_err());
+        assert!(parse_values("1,NaN").is_err());
+        assert_eq!(parse_values(&format!("1:{}:1", MAX_SWEEP_VALUES)).unwrap().len(), MAX_SWEEP_VALUES);
+    }
+
+    #[test]
+    fn small_sweep()
+    {
+        let base = SimulationConfig { width: 24, height: 24, ..SimulationConfig::default() };
+
+        let mut sweep = Sweep::new(base);
+        sweep.sensor_angles = vec![22.5, 45.0];
+        sweep.decays = vec![0.0, 0.1, 0.2];
+        sweep.steps = 5;
+        sweep.thumbnail_size = 8;
+
+        let results = sweep.run().unwrap();
+
+        assert_eq!(results.len(), 6);
+        assert!(results.iter().enumerate().all(|(i, result)| result.index == i));
+        assert_eq!(results[4].parameters.sensor_angle, 45.0);
+        assert_eq!(results[4].parameters.decay, 0.1);
+        assert_eq!((results[0].thumbnail_width, results[0].thumbnail.len()), (8, 64));
+
+        // equal parameters give equal runs
+        assert_eq!(sweep.run_one(0, results[0].parameters).unwrap().thumbnail, results[0].thumbnail);
+
+        let mut csv = Vec::new();
+        write_csv(&results, &mut csv).unwrap();
+
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 7);
+        assert!(csv.starts_with(CSV_HEADER));
+
+        // one run per pair of decay and sensor angle
+        let diagram = phase_diagram(&results, SweepAxis::Decay, SweepAxis::SensorAngle);
+        let lines: Vec<&str> = diagram.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "decay\\sensor_angle,22.5,45");
+        assert!(lines[2].starts_with("0.1,"));
+        assert_eq!(lines[2].split(',').count(), 3);
+        assert_eq!(sweep.varied_axes(), vec![SweepAxis::SensorAngle, SweepAxis::Decay]);
+        assert_eq!(SweepAxis::from_name("rotation-angle"), Some(SweepAxis::RotationAngle));
+
+        // results stream in order, and an error stops the sweep
+        let mut indices = Vec::new();
+        let streamed = sweep.run_each(|result| {
+            indices.push(result.index);
+            if result.index == 2 { Err(ConfigError::Invalid(String::from("stop"))) } else { Ok(()) }
+        });
+        assert!(streamed.is_err());
+        assert_eq!(indices, vec![0, 1, 2]);
+
+        sweep.decays = vec![2.0];
+        assert!(matches!(sweep.run(), Err(ConfigError::Invalid(_))));
+
+        // six modest ranges multiply into too many runs
+        let values: Vec<f64> = (0..20).map(|i| i as f64).collect();
+        sweep.sensor_angles = values.clone();
+        sweep.rotation_angles = values.clone();
+        sweep.sensor_offsets = (0..20).collect();
+        sweep.step_sizes = (1..21).collect();
+        sweep.depositions = values.iter().map(|value| *value as Concentration).collect();
+        sweep.decays = values;
+        assert_eq!(sweep.run_count(), Some(64_000_000));
+        assert!(matches!(sweep.combinations(), Err(ConfigError::Invalid(_))));
+        assert!(matches!(sweep.run(), Err(ConfigError::Invalid(_))));
+    }
+}
@@ -0,0 +1,279 @@
+//! Texture descriptors of the thresholded trail: box-counting fractal dimension, lacunarity and the radially
+//! averaged power spectrum.
+//!
+//! The descriptors only need a binary mask, so the same numbers can be measured on photographs of real plasmodia
+//! (see `trail_from_image`) and compared with simulated networks.
+
+use std::fmt::Display;
+use std::io::{self, Write};
+
+use super::analysis::*;
+use super::layout::*;
+use super::plane::*;
+use super::simulation::*;
+use super::spectrum::*;
+use super::trail_map::*;
+
+/// `text` as a CSV field, in quotes (with inner quotes doubled) when it holds a comma, quote or line break
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        String::from(text)
+    }
+}
+
+/// Box sizes doubling from 1 up to a quarter of the shorter side, so the largest boxes still tile the map a few times
+pub fn default_box_sizes(width: usize, height: usize) -> Vec<usize> {
+    let largest = (width.min(height) / 4).max(1);
+
+    std::iter::successors(Some(1usize), |size| Some(size * 2)).take_while(|size| *size <= largest).collect()
+}
+
+/// Number of `box_size` x `box_size` boxes of a grid over the mask (partial boxes at the edges included) holding
+/// at least one set pixel
+pub fn box_count(mask: &[bool], width: usize, height: usize, box_size: usize) -> usize {
+    assert_eq!(mask.len(), width * height, "mask does not match dimensions");
+
+    let box_size = box_size.max(1);
+    let columns = width.div_ceil(box_size);
+    let mut occupied = vec![false; columns * height.div_ceil(box_size)];
+
+    for (index, set) in mask.iter().enumerate() {
+        if *set {
+            occupied[(index / width / box_size) * columns + (index % width) / box_size] = true;
+        }
+    }
+
+    occupied.iter().filter(|occupied| **occupied).count()
+}
+
+/// Box-counting (Minkowski-Bouligand) dimension: the slope of `ln N(s)` against `ln 1/s` for the box counts
+/// `N(s)` of the given sizes. Close to 1 for lines and 2 for filled regions, 0 for an empty mask
+pub fn fractal_dimension(mask: &[bool], width: usize, height: usize, box_sizes: &[usize]) -> f64 {
+    let points: Vec<(f64, f64)> = box_sizes.iter()
+        .map(|size| (*size as f64, box_count(mask, width, height, *size) as f64))
+        .collect();
+
+    log_log_slope(&points).map(|slope| -slope).unwrap_or(0f64)
+}
+
+/// Gliding box lacunarity: for every position of a `box_size` x `box_size` box inside the mask, `M` is the number
+/// of set pixels it covers, and the lacunarity is `E[M^2] / E[M]^2`. 1 for a translation invariant pattern and
+/// growing with the size and number of gaps, 0 for an empty mask or a box larger than the mask
+pub fn lacunarity(mask: &[bool], width: usize, height: usize, box_size: usize) -> f64 {
+    assert_eq!(mask.len(), width * height, "mask does not match dimensions");
+
+    let box_size = box_size.max(1);
+
+    if box_size > width || box_size > height {
+        return 0f64;
+    }
+
+    // summed area table with a row and column of zeros in front
+    let stride = width + 1;
+    let mut sums = vec![0u64; stride * (height + 1)];
+
+    for y in 0..height {
+        for x in 0..width {
+            sums[(y + 1) * stride + x + 1] = mask[y * width + x] as u64
+                + sums[y * stride + x + 1]
+                + sums[(y + 1) * stride + x]
+                - sums[y * stride + x];
+        }
+    }
+
+    let (mut first, mut second, mut boxes) = (0f64, 0f64, 0f64);
+
+    for y in 0..=height - box_size {
+        for x in 0..=width - box_size {
+            let (x2, y2) = (x + box_size, y + box_size);
+            let mass = (sums[y2 * stride + x2] + sums[y * stride + x] - sums[y * stride + x2] - sums[y2 * stride + x]) as f64;
+
+            first += mass;
+            second += mass * mass;
+            boxes += 1f64;
+        }
+    }
+
+    if first == 0f64 {
+        0f64
+    } else {
+        (second / boxes) / (first / boxes).powi(2)
+    }
+}
+
+/// Grayscale version of an image for measuring its texture, brighter pixels map to higher concentrations.
+/// `invert` for images where the plasmodium is darker than the background
+pub fn trail_from_image(image: &Layout, invert: bool) -> TrailMap {
+    let mut trail_map = TrailMap::new(image.width, image.height);
+
+    for (value, [r, g, b]) in trail_map.data.iter_mut().zip(image.pixels.iter()) {
+        let luma = 0.299f64 * *r as f64 + 0.587f64 * *g as f64 + 0.114f64 * *b as f64;
+
+        *value = (if invert { 255f64 - luma } else { luma }) as Concentration;
+    }
+
+    trail_map
+}
+
+/// Settings for `Texture::measure`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextureConfig {
+    /// Separates the pattern from the background
+    pub threshold: Threshold,
+    /// Box sizes (in pixels) for the fractal dimension and lacunarity, empty for `default_box_sizes`
+    pub box_sizes: Vec<usize>
+}
+
+/// Texture descriptors of a thresholded map
+#[derive(Clone, Debug, PartialEq)]
+pub struct Texture {
+    /// Fraction of the map above the threshold
+    pub coverage: f64,
+    /// See `fractal_dimension`
+    pub fractal_dimension: f64,
+    /// Box size and `box_count` per size used for the fractal dimension
+    pub box_counts: Vec<(usize, usize)>,
+    /// Box size and `lacunarity` per size
+    pub lacunarity: Vec<(usize, f64)>,
+    /// Radially averaged power spectrum of the mask
+    pub spectrum: RadialSpectrum
+}
+
+/// Column names of `Texture::write_csv_row`, after the label column
+pub const TEXTURE_CSV_COLUMNS: &str = "coverage,fractal_dimension,spectral_slope,dominant_wavelength,lacunarity,radial_spectrum";
+
+impl Texture {
+    pub fn measure(trail_map: &TrailMap, config: &TextureConfig) -> Self {
+        let (width, height) = (trail_map.width(), trail_map.height());
+        let mask = threshold_mask(trail_map, config.threshold);
+
+        let box_sizes = if config.box_sizes.is_empty() { default_box_sizes(width, height) } else { config.box_sizes.clone() };
+
+        Self {
+            coverage: coverage(trail_map, config.threshold),
+            fractal_dimension: fractal_dimension(&mask, width, height, &box_sizes),
+            box_counts: box_sizes.iter().map(|size| (*size, box_count(&mask, width, height, *size))).collect(),
+            lacunarity: box_sizes.iter().map(|size| (*size, lacunarity(&mask, width, height, *size))).collect(),
+            spectrum: RadialSpectrum::of_mask(&mask, width, height)
+        }
+    }
+
+    pub fn from_simulation(simulation: &Simulation, config: &TextureConfig) -> Self {
+        Self::measure(&simulation.trail_map, config)
+    }
+
+    /// Writes the descriptors as a CSV row after a label (e.g. the step or image name), quoted when needed.
+    /// Lacunarity is written as space separated `size:value` pairs and the radial spectrum as space separated powers
+    /// from the lowest ring
+    pub fn write_csv_row<W: Write, L: Display>(&self, label: L, mut writer: W) -> io::Result<()> {
+        let lacunarity: Vec<String> = self.lacunarity.iter().map(|(size, value)| format!("{}:{}", size, value)).collect();
+        let spectrum: Vec<String> = self.spectrum.power.iter().map(|power| power.to_string()).collect();
+
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{}",
+            csv_field(&label.to_string()),
+            self.coverage,
+            self.fractal_dimension,
+            self.spectrum.slope().unwrap_or(0f64),
+            self.spectrum.dominant_wavelength().unwrap_or(0f64),
+            lacunarity.join(" "),
+            spectrum.join(" ")
+        )
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    /// Sierpinski carpet of `3^depth` pixels a side
+    fn carpet(depth: u32) -> (Vec<bool>, usize) {
+        let size = 3usize.pow(depth);
+
+        let mask = (0..size * size).map(|index| {
+            let (mut x, mut y) = (index % size, index / size);
+
+            while x > 0 || y > 0 {
+                if x % 3 == 1 && y % 3 == 1 {
+                    return false;
+                }
+                x /= 3;
+                y /= 3;
+            }
+
+            true
+        }).collect();
+
+        (mask, size)
+    }
+
+    #[test]
+    fn dimension()
+    {
+        let (mask, size) = carpet(4);
+        let expected = 8f64.ln() / 3f64.ln();
+        assert!((fractal_dimension(&mask, size, size, &[1, 3, 9, 27]) - expected).abs() < 1e-9);
+
+        let full = vec![true; 64 * 64];
+        assert!((fractal_dimension(&full, 64, 64, &default_box_sizes(64, 64)) - 2.0).abs() < 1e-9);
+
+        let line: Vec<bool> = (0..64 * 64).map(|index| index / 64 == 10).collect();
+        assert!((fractal_dimension(&line, 64, 64, &default_box_sizes(64, 64)) - 1.0).abs() < 1e-9);
+
+        assert_eq!(fractal_dimension(&[false; 64], 8, 8, &[1, 2]), 0.0);
+        assert_eq!(default_box_sizes(64, 40), vec![1, 2, 4, 8]);
+
+        // partial boxes at the edges count
+        assert_eq!(box_count(&[true; 5 * 5], 5, 5, 2), 9);
+    }
+
+    #[test]
+    fn gaps()
+    {
+        let full = vec![true; 16 * 16];
+        assert!((lacunarity(&full, 16, 16, 4) - 1.0).abs() < 1e-9);
+
+        // larger gaps between the same amount of mass give a higher lacunarity
+        let fine: Vec<bool> = (0..32 * 32).map(|index| (index % 32) % 2 == 0).collect();
+        let coarse: Vec<bool> = (0..32 * 32).map(|index| (index % 32) % 8 < 4).collect();
+        assert!(lacunarity(&coarse, 32, 32, 2) > lacunarity(&fine, 32, 32, 2));
+
+        // single pixel boxes: E[M^2] / E[M]^2 = 1 / coverage
+        assert!((lacunarity(&fine, 32, 32, 1) - 2.0).abs() < 1e-9);
+
+        assert_eq!(lacunarity(&full, 16, 16, 17), 0.0);
+        assert_eq!(lacunarity(&[false; 16], 4, 4, 2), 0.0);
+    }
+
+    #[test]
+    fn measure()
+    {
+        let image = Layout { width: 8, height: 8, pixels: (0..64).map(|index| if index % 8 < 2 { [0, 0, 0] } else { [255, 255, 255] }).collect() };
+
+        // a dark quarter on a light background, measured after inverting
+        let trail_map = trail_from_image(&image, true);
+        let texture = Texture::measure(&trail_map, &TextureConfig { threshold: Threshold::FractionOfMax(0.5), box_sizes: vec![1, 2] });
+
+        assert_eq!(texture.coverage, 0.25);
+        assert_eq!(texture.box_counts, vec![(1, 16), (2, 4)]);
+        assert!((texture.fractal_dimension - 2.0).abs() < 1e-9);
+        assert_eq!(texture.spectrum.size, 8);
+
+        let mut row = Vec::new();
+        texture.write_csv_row(0, &mut row).unwrap();
+
+        let row = String::from_utf8(row).unwrap();
+        assert_eq!(row.split(',').count(), TEXTURE_CSV_COLUMNS.split(',').count() + 1);
+        assert!(row.contains(",1:4 2:"));
+
+        // labels such as file names are quoted when they would break the row
+        let mut row = Vec::new();
+        texture.write_csv_row("plasmodium, \"day 2\".png", &mut row).unwrap();
+        assert!(String::from_utf8(row).unwrap().starts_with("\"plasmodium, \"\"day 2\"\".png\","));
+        assert_eq!(csv_field("plain.png"), "plain.png");
+    }
+}
@@ -0,0 +1,175 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "web")]
+use web_sys::ImageData;
+#[cfg(feature = "web")]
+use wasm_bindgen::Clamped;
+
+use super::boundary::*;
+use super::config::*;
+use super::diffusion::*;
+use super::image::*;
+use super::plane::*;
+use super::point::*;
+
+/// Numeric type used for chemoattractant concentration, `f64` with the `double-precision` feature
+#[cfg(not(feature = "double-precision"))]
+pub type Concentration = f32;
+#[cfg(feature = "double-precision")]
+pub type Concentration = f64;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TrailMap {
+    pub data: Vec<Concentration>,
+    width: usize,
+    height: usize,
+    /// Second buffer and scratch space for `diffuse`
+    #[serde(skip)]
+    buffers: DiffusionBuffers
+}
+
+#[cfg(feature = "web")]
+impl From<TrailMap> for ImageData {
+    fn from(trail_map: TrailMap) -> ImageData {
+        ImageData::new_with_u8_clamped_array_and_sh(Clamped(&trail_map.to_rgba()), trail_map.width() as u32, trail_map.height() as u32).unwrap()
+    }
+}
+
+impl TrailMap {
+    pub fn new(width: usize, height: usize) -> Self {
+
+        let data = vec![0 as Concentration; width * height];
+
+        Self {
+            data,
+            width,
+            height,
+            buffers: DiffusionBuffers::default()
+        }
+    }
+
+    pub fn new_random<R: Rng>(width: usize, height: usize, rng: &mut R) -> Self {
+        let mut data = vec![0 as Concentration; width * height];
+
+        for el in data.iter_mut() {
+                *el = (rng.gen::<f64>() * 255f64) as Concentration;
+        }
+
+        Self {
+            data,
+            width,
+            height,
+            buffers: DiffusionBuffers::default()
+        }
+    }
+
+    /// Removes a fraction (between 0-1) of the chemoattractant everywhere on the map
+    pub fn decay(&mut self, factor: f64) {
+        if factor <= 0f64 {
+            return;
+        }
+
+        let retained = (1f64 - factor).max(0f64) as Concentration;
+
+        for value in self.data.iter_mut() {
+            *value *= retained;
+        }
+    }
+
+    /// Moves every pixel a fraction `rate` (between 0-1) of the way towards the weighted mean of its neighbourhood
+    /// under `kernel`, see `diffusion`. Reuses the same buffers every step instead of allocating a new map
+    pub fn diffuse<F: Fn(usize) -> bool>(&mut self, kernel: &Kernel, rate: Concentration, boundary: BoundaryMode, is_obstacle: F) {
+        super::diffusion::diffuse(&mut self.data, &mut self.buffers, self.width, self.height, kernel, rate, boundary, is_obstacle);
+    }
+
+    /// Average concentration in a sensor window of the given width and shape centred on `point`,
+    /// pixels that the boundary mode places outside of the map count as empty
+    pub fn sample(&self, point: Point<f64>, width: usize, shape: SensorShape, boundary: BoundaryMode) -> Concentration {
+        self.sample_with(point, width, shape, boundary, |value| *value as f64) as Concentration
+    }
+
+    /// Highest concentration on the map
+    pub fn max_value(&self) -> Concentration {
+        self.data.iter().cloned().fold(0 as Concentration, Concentration::max)
+    }
+
+    /// Maps the concentration to 0-255 for display, with `max` (and anything above it) becoming white
+    pub fn normalize(&self, max: Concentration) -> Vec<u8> {
+        if max <= 0 as Concentration {
+            return vec![0u8; self.data.len()];
+        }
+
+        self.data.iter()
+            .map(|value| ((value / max).clamp(0 as Concentration, 1 as Concentration) * 255 as Concentration).round() as u8)
+            .collect()
+    }
+
+    /// Grayscale image of the chemoattractant concentration, normalized to the highest value on the map
+    pub fn to_grayscale(&self) -> Vec<u8> {
+        self.normalize(self.max_value())
+    }
+
+    /// RGBA version of `to_grayscale`
+    pub fn to_rgba(&self) -> Vec<u8> {
+        grayscale_to_rgba(&self.to_grayscale())
+    }
+
+    /// PNG encoded grayscale image of the chemoattractant concentration
+    pub fn to_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        encode_png_grayscale(self.width, self.height, &self.to_grayscale())
+    }
+
+    // Fills a circular area of a given size, with an optional gradient
+    // pub fn fill(&mut self, point: Point, radius: f64) {
+
+    // }
+}
+
+impl Plane<Concentration> for TrailMap {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn data(&self) -> &Vec<Concentration> {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+
+    use super::*;
+
+    #[test]
+    fn normalize()
+    {
+        let mut trail_map = TrailMap::new(4, 1);
+        trail_map.data = vec![0.0, 0.5, 2.0, 4.0];
+
+        assert_eq!(trail_map.to_grayscale(), vec![0, 32, 128, 255]);
+        assert_eq!(trail_map.normalize(2.0), vec![0, 64, 255, 255]);
+        assert_eq!(TrailMap::new(2, 1).to_grayscale(), vec![0, 0]);
+    }
+
+    #[test]
+    fn sample_window()
+    {
+        let mut trail_map = TrailMap::new(5, 5);
+        trail_map.data[12] = 9.0;
+        trail_map.data[13] = 9.0;
+
+        let centre = Point::new(2f64, 2f64);
+
+        assert_eq!(trail_map.sample(centre, 1, SensorShape::Square, BoundaryMode::Periodic), 9.0);
+        assert_eq!(trail_map.sample(centre, 3, SensorShape::Square, BoundaryMode::Periodic), 2.0);
+        // the circle of diameter 3 leaves out the corners
+        assert_eq!(trail_map.sample(centre, 3, SensorShape::Circle, BoundaryMode::Periodic), 18.0 / 5.0);
+        // outside the map is empty for absorbing edges, but wraps for periodic ones
+        assert_eq!(trail_map.sample(Point::new(4f64, 2f64), 3, SensorShape::Square, BoundaryMode::Absorbing), 1.0);
+        assert_eq!(trail_map.sample(Point::new(1f64, 2f64), 3, SensorShape::Square, BoundaryMode::Periodic), 1.0);
+    }
+}
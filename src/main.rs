@@ -1,25 +1,15 @@
-#![feature(unboxed_closures)]
-#![feature(fn_traits)]
-
-use std::vec::{self, IntoIter};
-
-use model::cell::{CellMap};
-use model::trail_map::*;
-use model::config::SimulationConfig;
-use model::simulation::Simulation;
-use model::point::*;
+use slime::config::SimulationConfig;
+use slime::nutrient::NutrientSource;
+use slime::point::Point;
+use slime::simulation::Simulation;
 use yew::prelude::*;
 use web_sys::HtmlCanvasElement;
 use web_sys::CanvasRenderingContext2d;
 use wasm_bindgen::JsCast;
-use web_sys::ImageData;
 use wasm_bindgen::Clamped;
+use web_sys::ImageData;
 use yew::services::console::ConsoleService as Console;
 
-use rand::thread_rng;
-
-mod model;
-
 enum Msg {
     Step,
     TrailCanvasClick(MouseEvent),
@@ -51,15 +41,9 @@ fn get_context(canvas: HtmlCanvasElement) -> CanvasRenderingContext2d {
 }
 
 fn create_simulation(size: usize) -> Simulation {
-    let mut config = SimulationConfig::default();
+    let config = SimulationConfig { width: size, height: size, seed: rand::random(), ..SimulationConfig::default() };
 
-    config.width = size;
-    config.height = size;
-
-    let cell_map = CellMap::new_random(config.width, config.height, config.sensor_config.clone(),  0.1f64);
-    let trail_map = TrailMap::new_random(config.width, config.height);
-
-    Simulation::new(config, cell_map, trail_map)
+    Simulation::new_random(config, 0.1f64).expect("the canvas fits in the occupancy grid")
 }
 
 impl Component for Model {
@@ -94,7 +78,7 @@ impl Component for Model {
 
                     let image_data: ImageData = self.simulation.cell_map.render();
 
-                    context.put_image_data(&image_data, 0f64, 0f64);
+                    context.put_image_data(&image_data, 0f64, 0f64).unwrap();
                     
                 }
 
@@ -103,11 +87,17 @@ impl Component for Model {
 
                     let image_data: ImageData = self.simulation.trail_map.clone().into();
 
-                    context.put_image_data(&image_data, 0f64, 0f64);
+                    context.put_image_data(&image_data, 0f64, 0f64).unwrap();
                 }
 
                 if let Some(composite_canvas) = self.composite_canvas.cast::<HtmlCanvasElement>() {
-                    //@TODO: implement this
+                    let context = get_context(composite_canvas);
+
+                    let data = self.simulation.to_composite_rgba();
+                    let (width, height) = (self.simulation.config.width as u32, self.simulation.config.height as u32);
+                    let image_data = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&data), width, height).unwrap();
+
+                    context.put_image_data(&image_data, 0f64, 0f64).unwrap();
                 }
 
                 self.live_cell_count = self.simulation.cell_map.live_cells();
@@ -122,7 +112,10 @@ impl Component for Model {
             Msg::TrailCanvasClick(event) => {
                 let (x, y) = (event.offset_x(), event.offset_y());
 
-                Console::log(&format!("Got a mouse click @ {:?},{:?}", x, y));
+                Console::log(&format!("Placing food source @ {:?},{:?}", x, y));
+
+                let position = Point::new(x as f64, y as f64);
+                self.simulation.nutrients.push(NutrientSource::disc(position, 3f64, 50.0));
 
                 true
             },
@@ -141,25 +134,6 @@ impl Component for Model {
         }
     }
 
-    fn rendered(&mut self, _: bool)
-    {
-        if let Some(canvas) = self.cell_canvas.cast::<HtmlCanvasElement>() {
-
-            let context = get_context(canvas);
-
-
-            let pixel = [255, 110, 100, 255];
-
-            let mut data = Vec::new();
-
-            let clamped: Clamped<&[u8]> = Clamped(&data);
-
-            
-
-            
-        }
-    }
-
     fn change(&mut self, _props: Self::Properties) -> ShouldRender {
         // Should only return "true" if new properties are different to
         // previously received properties.
@@ -169,7 +143,7 @@ impl Component for Model {
 
     fn view(&self) -> Html {
         let trail_canvas_click = self.link.callback(|e: MouseEvent| Msg::TrailCanvasClick(e));
-        let cell_canvas_click = self.link.callback(|e: MouseEvent| Msg::CellCanvasClick(e));
+        let _cell_canvas_click = self.link.callback(|e: MouseEvent| Msg::CellCanvasClick(e));
         //e.data().unwrap_or(String::from("1")).parse::<usize>().unwrap_or(1))
 
         let step_number_change = self.link.callback(|e: ChangeData| {
@@ -1,6 +0,0 @@
-pub mod simulation;
-pub mod plane;
-pub mod cell;
-pub mod config;
-pub mod point;
-pub mod trail_map;
\ No newline at end of file